@@ -0,0 +1,249 @@
+//broadphase.rs
+//cheap searches for pairs of bounding boxes that might be touching, narrowed down exactly by physics
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#![allow(dead_code)]
+use std::collections::HashMap;
+use super::kinematics::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub x1 : Float,
+    pub y1 : Float,
+    pub x2 : Float,
+    pub y2 : Float,
+}
+
+impl Aabb {
+    pub fn around(x : Float, y : Float, r : Float) -> Aabb {
+        Aabb { x1 : x - r, y1 : y - r, x2 : x + r, y2 : y + r }
+    }
+    pub fn overlaps(&self, other : &Aabb) -> bool {
+        self.x1 <= other.x2 && other.x1 <= self.x2 && self.y1 <= other.y2 && other.y1 <= self.y2
+    }
+    pub fn width(&self) -> Float {
+        self.x2 - self.x1
+    }
+    pub fn height(&self) -> Float {
+        self.y2 - self.y1
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+pub trait BroadPhase {
+    //candidate pairs (i, j) with i < j, sorted, whose boxes overlap
+    //bounds are indexed the same way between calls, so implementations may keep state from the last search
+    fn pairs(&mut self, bounds : &[Aabb]) -> Vec<(usize, usize)>;
+    fn name(&self) -> &'static str;
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+pub struct BruteForce;
+
+impl BroadPhase for BruteForce {
+    fn pairs(&mut self, bounds : &[Aabb]) -> Vec<(usize, usize)> {
+        //O(n^2) reference search, every box against every later box
+        let mut ret = Vec::<(usize, usize)>::new();
+        for i in 0..bounds.len() {
+            for j in (i + 1)..bounds.len() {
+                if bounds[i].overlaps(&bounds[j]) {
+                    ret.push((i, j));
+                }
+            }
+        }
+        ret
+    }
+    fn name(&self) -> &'static str {
+        "brute force"
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+pub struct SpatialGrid {
+    pub cell_size : Option<Float>, //None sizes cells off the boxes each search, twice their average extent
+    cells : HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new() -> Self {
+        SpatialGrid {
+            cell_size : None,
+            cells : HashMap::new(),
+        }
+    }
+    pub fn with_cell_size(cell_size : Float) -> Self {
+        SpatialGrid {
+            cell_size : Some(cell_size),
+            cells : HashMap::new(),
+        }
+    }
+    fn pick_cell_size(&self, bounds : &[Aabb]) -> Float {
+        if let Some(size) = self.cell_size {
+            return size;
+        }
+        let total : Float = bounds.iter().map(|b| b.width().max(b.height())).sum();
+        let size = 2.0 * total / bounds.len() as Float;
+        if size > 0.0 && size.is_finite() { size } else { 1.0 }
+    }
+}
+
+impl BroadPhase for SpatialGrid {
+    fn pairs(&mut self, bounds : &[Aabb]) -> Vec<(usize, usize)> {
+        //each box is filed under every cell it covers, then boxes sharing a cell are tested against each other
+        //a pair can share several cells, so the result is sorted and deduplicated
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        if bounds.len() < 2 {
+            return Vec::new();
+        }
+        let size = self.pick_cell_size(bounds);
+        let cell_of = |v : Float| (v / size).floor() as i64;
+        for (i, b) in bounds.iter().enumerate() {
+            for cx in cell_of(b.x1)..=cell_of(b.x2) {
+                for cy in cell_of(b.y1)..=cell_of(b.y2) {
+                    self.cells.entry((cx, cy)).or_default().push(i);
+                }
+            }
+        }
+        let mut ret = Vec::<(usize, usize)>::new();
+        for cell in self.cells.values() {
+            for a in 0..cell.len() {
+                for b in (a + 1)..cell.len() {
+                    let (i, j) = (cell[a], cell[b]); //filed in index order, so i < j
+                    if bounds[i].overlaps(&bounds[j]) {
+                        ret.push((i, j));
+                    }
+                }
+            }
+        }
+        self.cells.retain(|_, cell| !cell.is_empty());
+        ret.sort_unstable();
+        ret.dedup();
+        ret
+    }
+    fn name(&self) -> &'static str {
+        "spatial grid"
+    }
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+pub struct SweepAndPrune {
+    order : Vec<usize>, //box indices sorted by left edge as of the last search
+    active : Vec<usize>,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> Self {
+        SweepAndPrune {
+            order : Vec::new(),
+            active : Vec::new(),
+        }
+    }
+}
+
+impl Default for SweepAndPrune {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BroadPhase for SweepAndPrune {
+    fn pairs(&mut self, bounds : &[Aabb]) -> Vec<(usize, usize)> {
+        //sorts boxes along x then sweeps left to right, only testing boxes whose x ranges overlap
+        //balls barely move between ticks, so the last order is nearly sorted and insertion sort is close to linear
+        if self.order.len() != bounds.len() {
+            self.order = (0..bounds.len()).collect();
+        }
+        for k in 1..self.order.len() {
+            let mut m = k;
+            while m > 0 && bounds[self.order[m - 1]].x1 > bounds[self.order[m]].x1 {
+                self.order.swap(m - 1, m);
+                m -= 1;
+            }
+        }
+        let mut ret = Vec::<(usize, usize)>::new();
+        self.active.clear();
+        for &i in &self.order {
+            self.active.retain(|&j| bounds[j].x2 >= bounds[i].x1);
+            for &j in &self.active {
+                if bounds[i].y1 <= bounds[j].y2 && bounds[j].y1 <= bounds[i].y2 {
+                    ret.push((i.min(j), i.max(j)));
+                }
+            }
+            self.active.push(i);
+        }
+        ret.sort_unstable();
+        ret
+    }
+    fn name(&self) -> &'static str {
+        "sweep and prune"
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn scattered_bounds(n : usize, seed : u64, spread : Float, max_r : Float) -> Vec<Aabb> {
+        //deterministic pseudo random boxes from a linear congruential generator
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 40) as Float / (1u64 << 24) as Float
+        };
+        (0..n).map(|_| {
+            let (x, y, r) = ((next() - 0.5) * spread, (next() - 0.5) * spread, next() * max_r + 0.01);
+            Aabb::around(x, y, r)
+        }).collect()
+    }
+    fn all_broad_phases() -> Vec<Box<dyn BroadPhase>> {
+        vec![Box::new(BruteForce), Box::new(SpatialGrid::new()), Box::new(SweepAndPrune::new())]
+    }
+    #[test]
+    fn empty_and_single() {
+        for mut broad_phase in all_broad_phases() {
+            assert!(broad_phase.pairs(&[]).is_empty(), "{}", broad_phase.name());
+            assert!(broad_phase.pairs(&[Aabb::around(0.0, 0.0, 1.0)]).is_empty(), "{}", broad_phase.name());
+        }
+    }
+    #[test]
+    fn grid_matches_brute_force() {
+        for (seed, cell_size) in [(1, None), (2, Some(0.5)), (3, Some(50.0)), (4, None)] {
+            let bounds = scattered_bounds(500, seed, 100.0, 3.0);
+            let mut grid = SpatialGrid { cell_size, cells : HashMap::new() };
+            let expected = BruteForce.pairs(&bounds);
+            assert!(!expected.is_empty());
+            assert_eq!(grid.pairs(&bounds), expected);
+            //reusing the grid's cells must not leak the last search into the next
+            assert_eq!(grid.pairs(&bounds[..250]), BruteForce.pairs(&bounds[..250]));
+        }
+    }
+    #[test]
+    fn sweep_and_prune_matches_brute_force() {
+        let mut sweep = SweepAndPrune::new();
+        let mut bounds = scattered_bounds(500, 5, 100.0, 3.0);
+        for step in 0..10 {
+            //drift the boxes so the kept order goes slightly stale between searches, like balls between ticks
+            for (k, b) in bounds.iter_mut().enumerate() {
+                let dx = ((k * 7 + step) % 11) as Float * 0.05 - 0.25;
+                b.x1 += dx;
+                b.x2 += dx;
+            }
+            assert_eq!(sweep.pairs(&bounds), BruteForce.pairs(&bounds));
+        }
+        assert_eq!(sweep.pairs(&bounds[..100]), BruteForce.pairs(&bounds[..100]));
+    }
+}
@@ -1,5 +1,6 @@
 use super::kinematics;
 use graphics::Transformed;
+use kinematics::Float;
 use kinematics::Polynomial;
 use kinematics::Unit;
 use kinematics::Units;
@@ -7,9 +8,21 @@ use kinematics::Var;
 use kinematics::Monomial;
 use super::physics;
 use physics::Space;
+use physics::BallId;
 use physics::AccelxyFunction;
 use physics::GRAVITY_MPS2;
+use super::obstacle;
+use obstacle::Obstacle;
+use obstacle::Polygon;
+use obstacle::Segment;
+use super::link::RopeEnd;
 use super::gl::Window;
+use super::timestep;
+use timestep::FixedTimestep;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub fn shadowize(color : [f32; 4]) -> [f32; 4] {
     let mut ret = color;
@@ -20,22 +33,122 @@ pub fn shadowize(color : [f32; 4]) -> [f32; 4] {
     ret
 }
 
-pub fn bind(mut window : Window, mut space : Space, fps : u64, tps : u64) -> Window {
+//most physics steps a frame may run before the simulation falls behind the clock instead
+const MAX_SUBSTEPS : usize = 8;
+
+struct Simulation {
+    space : Space,
+    timestep : FixedTimestep,
+    previous : HashMap<BallId, (Float, Float)>, //ball positions before the latest step, drawn positions are blended from these
+}
+
+pub fn bind(mut window : Window, space : Space, fps : u64, tps : u64) -> Window {
     let xscale : f64 = window.width as f64 / (space.x2 as f64 - space.x1 as f64);
     let xcenter : f64 = -space.x1 as f64;
     let yscale : f64 = window.height as f64 / (space.y1 as f64 - space.y2 as f64);
     let ycenter : f64 = -space.y2 as f64;
     let floor_p_y = window.height as f64 + (space.floor as f64 - space.y1 as f64) * yscale;
+    let to_screen = move |x : Float, y : Float| [x as f64 * xscale + xcenter * xscale, y as f64 * yscale + ycenter * yscale];
+
+    //physics steps from the update callback at a fixed dt, rendering only reads the space
+    let simulation = Rc::new(RefCell::new(Simulation { space, timestep : FixedTimestep::new(1.0 / tps as Float, MAX_SUBSTEPS), previous : HashMap::new() }));
+    let updating = Rc::clone(&simulation);
+    window.set_update_fn(Box::new(move |_utick, dt| {
+        let Simulation { space, timestep, previous } = &mut *updating.borrow_mut();
+        timestep.advance(dt as Float, |step| {
+            *previous = space.get_balls().iter().map(|ball| (ball.get_id(), (ball.get_x(), ball.get_y()))).collect();
+            space.tick(step);
+        });
+    }));
 
     window.set_render_fn(Box::new(move |rtick, utick, c, gl| {
-        space.tick(1.0 / tps as f32);
+        let simulation = simulation.borrow();
+        let space = &simulation.space;
+        let alpha = simulation.timestep.alpha();
         graphics::clear([0.5, 0.75, 0.85, 1.0], gl); //sky blue
         graphics::rectangle([0.6,0.4,0.2,1.0],   [0.0, floor_p_y,       window.width as f64, window.height as f64 - floor_p_y], c.transform, gl);
         graphics::rectangle([0.25,0.55,0.2,1.0], [0.0, floor_p_y - 5.0, window.width as f64, 10.0     ], c.transform, gl);
-        for ball in &space.balls {
+        for obstacle in &space.obstacles {
+            match obstacle {
+                Obstacle::Segment(segment) => {
+                    let (p1, p2) = (to_screen(segment.x1, segment.y1), to_screen(segment.x2, segment.y2));
+                    graphics::line([0.4,0.3,0.2,1.0], 3.0, [p1[0], p1[1], p2[0], p2[1]], c.transform, gl);
+                }
+                Obstacle::Polygon(polygon) => {
+                    let points : Vec<[f64; 2]> = polygon.vertices().iter().map(|&(x, y)| to_screen(x, y)).collect();
+                    graphics::polygon([0.4,0.3,0.2,1.0], &points, c.transform, gl);
+                }
+            }
+        }
+        //balls that weren't there before the latest step have nothing to blend from, so they are drawn where they are
+        //neither do balls that wrapped across the space during it, blending would streak them over the whole screen
+        let (half_width, half_height) = ((space.x2 - space.x1) / 2.0, (space.y2 - space.floor) / 2.0);
+        let drawn : HashMap<BallId, (Float, Float)> = space.get_balls().iter().map(|ball| {
+            let now = (ball.get_x(), ball.get_y());
+            (ball.get_id(), match simulation.previous.get(&ball.get_id()) {
+                Some(&before) if (now.0 - before.0).abs() <= half_width && (now.1 - before.1).abs() <= half_height => timestep::lerp(before, now, alpha),
+                _ => now,
+            })
+        }).collect();
+        let at = |id : BallId| drawn.get(&id).map(|&(x, y)| to_screen(x, y));
+        //rope balls are drawn as one line through the rope instead of as balls with links between them
+        //a ball the rope is tied to sits at one of its ends and is drawn as usual
+        let in_rope : HashSet<BallId> = space.ropes.iter().flat_map(|rope| rope.segments()).collect();
+        let roped = |id : BallId| in_rope.contains(&id);
+        //links go under the balls, springs thin and rods thick
+        let link = |a : BallId, b : BallId| {
+            let (p1, p2) = (at(a)?, at(b)?);
+            Some([p1[0], p1[1], p2[0], p2[1]])
+        };
+        for spring in &space.springs {
+            if let Some(line) = link(spring.a, spring.b) {
+                graphics::line([0.3,0.3,0.3,1.0], 1.5, line, c.transform, gl);
+            }
+        }
+        for constraint in space.constraints.iter().filter(|constraint| !roped(constraint.a) && !roped(constraint.b)) {
+            if let Some(line) = link(constraint.a, constraint.b) {
+                graphics::line([0.2,0.2,0.2,1.0], 3.0, line, c.transform, gl);
+            }
+        }
+        for pivot in space.pivots.iter().filter(|pivot| !roped(pivot.ball)) {
+            if let Some(end) = at(pivot.ball) {
+                let point = to_screen(pivot.x, pivot.y);
+                graphics::line([0.2,0.2,0.2,1.0], 3.0, [point[0], point[1], end[0], end[1]], c.transform, gl);
+                graphics::ellipse([0.2,0.2,0.2,1.0], graphics::ellipse::centered([point[0], point[1], 4.0, 4.0]), c.transform, gl);
+            }
+        }
+        for rope in &space.ropes {
+            let points : Vec<[f64; 2]> = rope.points.iter().filter_map(|&point| match point {
+                RopeEnd::Point(x, y) => Some(to_screen(x, y)),
+                RopeEnd::Ball(id) => at(id),
+            }).collect();
+            let thickness = rope.points.iter().find_map(|&point| match point {
+                RopeEnd::Ball(id) if roped(id) => space.get_ball(id).map(|ball| ball.get_radius() as f64 * xscale),
+                _ => None,
+            }).unwrap_or(2.0);
+            for pair in points.windows(2) {
+                graphics::Line::new_round([0.55,0.4,0.25,1.0], thickness).draw([pair[0][0], pair[0][1], pair[1][0], pair[1][1]], &c.draw_state, c.transform, gl);
+            }
+        }
+        for ball in space.get_balls() {
+            if roped(ball.get_id()) {
+                continue;
+            }
+            let (x, y) = drawn[&ball.get_id()];
+            let center = to_screen(x, y);
             graphics::ellipse(
                 ball.get_color(),
-                graphics::ellipse::centered([ball.get_x() as f64 * xscale + xcenter * xscale, ball.get_y() as f64 * yscale + ycenter * yscale, ball.get_radius() as f64 * xscale, ball.get_radius() as f64 * yscale]),
+                graphics::ellipse::centered([center[0], center[1], ball.get_radius() as f64 * xscale, ball.get_radius() as f64 * yscale]),
+                c.transform,
+                gl,
+            );
+
+            //spin marker, a spoke from the center to the rim at the ball's angle
+            let rim = to_screen(x + ball.get_radius() * ball.get_angle().cos(), y + ball.get_radius() * ball.get_angle().sin());
+            graphics::line(
+                shadowize(ball.get_color()),
+                ball.get_radius() as f64 * xscale * 0.1,
+                [center[0], center[1], rim[0], rim[1]],
                 c.transform,
                 gl,
             );
@@ -43,7 +156,7 @@ pub fn bind(mut window : Window, mut space : Space, fps : u64, tps : u64) -> Win
             //shadow
             graphics::ellipse(
                 shadowize(ball.get_color()),
-                graphics::ellipse::centered([ball.get_x() as f64 * xscale + xcenter * xscale, ball.get_y() as f64 * yscale + ycenter * yscale, ball.get_radius() as f64 * xscale, ball.get_radius() as f64 * yscale]),
+                graphics::ellipse::centered([center[0], center[1], ball.get_radius() as f64 * xscale, ball.get_radius() as f64 * yscale]),
                 c
                 //.rot_deg(180.0)
                 .trans(0.0, floor_p_y)
@@ -58,10 +171,7 @@ pub fn bind(mut window : Window, mut space : Space, fps : u64, tps : u64) -> Win
         }
 
     }));
-    // window.set_update_fn(Box::new(|utick| {
-    //     ret.space.tick(1.0 / 60.0);
-    // }));
-    window.set_ups(fps);
+    window.set_ups(tps);
     window.set_fps(fps);
     window
 }
@@ -98,7 +208,10 @@ pub fn world1() {
     myspace.new_ball_unchecked(2.0, 13.0, 5.0, -10.0, 0.5, 0.5, 0.8, 0.95, [0.0, 1.0, 0.0, 1.0]);
     myspace.new_ball_unchecked(4.0, 16.0, 10.0, -5.0, 3.0, 5.0, 0.8, 0.95, [0.0, 0.0, 1.0, 1.0]);
     myspace.new_ball_unchecked(40.0, 27.0, 100.0, -50.0, 15.0, 5.0, 0.95, 0.9999999, [0.1, 0.95, 1.0, 0.5]);
-    myspace.new_ball_unchecked(0.0, 10.0, 15.0 ,15.0, 5.0, 1.0, 1.1, 0.95, [0.9,0.01,0.4,1.0]);
+    //a ground bounce of 1 keeps this one's speed between the floor and the ceiling, any more and it gains speed on every trip
+    myspace.new_ball_unchecked(0.0, 10.0, 15.0 ,15.0, 5.0, 1.0, 1.0, 0.95, [0.9,0.01,0.4,1.0]);
+    myspace.obstacles.push(Obstacle::Segment(Segment::new(-50.0, 12.0, -30.0, 0.0)));
+    myspace.obstacles.push(Obstacle::Polygon(Polygon::regular(20.0, 8.0, 2.0, 6)));
     let mut mywindow = Window::new(1024,1024);
     let mut boundwindow = bind(mywindow, myspace, 165, 165);
     boundwindow.begin(String::from("Beautiful balls"), Window::DEFAULT_FLAGS);
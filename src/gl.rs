@@ -11,7 +11,7 @@ use piston::input::{RenderArgs, RenderEvent, UpdateArgs, UpdateEvent};
 use piston::window::WindowSettings;
 
 type RenderFunction = Box<dyn FnMut(u64, u64, graphics::Context, &mut GlGraphics)>;
-type UpdateFunction = Box<dyn FnMut(u64)>;
+type UpdateFunction = Box<dyn FnMut(u64, f64)>; //update tick and the seconds since the last update
 static OPENGL_VER : OpenGL = OpenGL::V4_0;
 
 pub struct Window {
@@ -23,7 +23,7 @@ pub struct Window {
     rtick : u64,
     utick : u64,
     renderf : Option<Box<dyn FnMut(u64, u64, graphics::Context, &mut GlGraphics)>>,
-    updatef : Option<Box<dyn FnMut(u64)>>,
+    updatef : Option<UpdateFunction>,
 
 }
 
@@ -108,14 +108,14 @@ impl Window {
     }
     pub fn update(&mut self, args : &UpdateArgs) {
         if let Some(f) = &mut self.updatef {
-            f(self.utick);
+            f(self.utick, args.dt);
         }
         self.utick += 1;
     }
     pub fn set_render_fn(&mut self, new_renderf : Box<dyn FnMut(u64, u64, graphics::Context, &mut GlGraphics)>) {
         self.renderf = Some(new_renderf);
     }
-    pub fn set_update_fn(&mut self, new_updatef : Box<dyn FnMut(u64)>) {
+    pub fn set_update_fn(&mut self, new_updatef : UpdateFunction) {
         self.updatef = Some(new_updatef);
     }
 }
@@ -129,7 +129,7 @@ impl Default for Window {
             let rect = graphics::rectangle::square(0.0, 0.0, ((rtick + utick) % 1024) as f64);
             graphics::rectangle(COOL_COLOR2, rect, c.transform, gl)
         };
-        let updatef = |utick : u64|{
+        let updatef = |utick : u64, dt : f64|{
             
         };
         Self::init(640, 480, Box::new(renderf), Box::new(updatef))
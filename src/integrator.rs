@@ -0,0 +1,88 @@
+//integrator.rs
+//numerical steps for motion the closed form polynomial paths can't follow, like accelerations depending on position or velocity
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#![allow(dead_code)]
+use super::kinematics::Float;
+
+type Vector = (Float, Float);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    #[default]
+    Analytic, //integrates the acceleration functions symbolically, exact but blind to position and velocity
+    SemiImplicitEuler, //first order, cheap and keeps orbits and springs from gaining energy
+    VelocityVerlet, //second order, exact for constant acceleration
+    Rk4, //fourth order runge kutta, four acceleration samples a step
+}
+
+fn add(a : Vector, b : Vector, scale : Float) -> Vector {
+    (a.0 + b.0 * scale, a.1 + b.1 * scale)
+}
+
+impl Integrator {
+    pub fn is_numerical(&self) -> bool {
+        *self != Integrator::Analytic
+    }
+    pub fn step(&self, p : Vector, v : Vector, dt : Float, accel : impl Fn(Vector, Vector, Float) -> Vector) -> (Vector, Vector) {
+        //advances position p and velocity v by dt, accel takes a position, velocity and the time into the step
+        //the analytic integrator has nothing to step with, so it leaves the state alone
+        match self {
+            Integrator::Analytic => (p, v),
+            Integrator::SemiImplicitEuler => {
+                let v1 = add(v, accel(p, v, 0.0), dt);
+                (add(p, v1, dt), v1)
+            }
+            Integrator::VelocityVerlet => {
+                let a0 = accel(p, v, 0.0);
+                let p1 = add(add(p, v, dt), a0, 0.5 * dt * dt);
+                let a1 = accel(p1, add(v, a0, dt), dt); //velocity dependent accelerations see a first order guess at the new velocity
+                (p1, add(add(v, a0, 0.5 * dt), a1, 0.5 * dt))
+            }
+            Integrator::Rk4 => {
+                let h = dt / 2.0;
+                let (k1p, k1v) = (v, accel(p, v, 0.0));
+                let (k2p, k2v) = (add(v, k1v, h), accel(add(p, k1p, h), add(v, k1v, h), h));
+                let (k3p, k3v) = (add(v, k2v, h), accel(add(p, k2p, h), add(v, k2v, h), h));
+                let (k4p, k4v) = (add(v, k3v, dt), accel(add(p, k3p, dt), add(v, k3v, dt), dt));
+                let sum = |a : Vector, b : Vector, c : Vector, d : Vector| (a.0 + 2.0 * b.0 + 2.0 * c.0 + d.0, a.1 + 2.0 * b.1 + 2.0 * c.1 + d.1);
+                (add(p, sum(k1p, k2p, k3p, k4p), dt / 6.0), add(v, sum(k1v, k2v, k3v, k4v), dt / 6.0))
+            }
+        }
+    }
+}
+
+pub fn hermite(p0 : Float, v0 : Float, p1 : Float, v1 : Float, dt : Float) -> [Float; 4] {
+    //coefficients of the cubic in time that starts at p0 moving at v0 and ends dt later at p1 moving at v1
+    let slope = (p1 - p0) / dt;
+    [p0, v0, (3.0 * slope - 2.0 * v0 - v1) / dt, (v0 + v1 - 2.0 * slope) / (dt * dt)]
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn hermite_matches_ends() {
+        let [c0, c1, c2, c3] = hermite(1.0, 2.0, 4.0, -1.0, 0.5);
+        let (p, v) = (|s : Float| c0 + c1 * s + c2 * s * s + c3 * s * s * s, |s : Float| c1 + 2.0 * c2 * s + 3.0 * c3 * s * s);
+        assert_eq!((p(0.0), v(0.0)), (1.0, 2.0));
+        assert!((p(0.5) - 4.0).abs() < 1e-5);
+        assert!((v(0.5) + 1.0).abs() < 1e-5);
+    }
+    #[test]
+    fn spring_period() {
+        //a unit spring, x'' = -x, returns to the start after 2 pi
+        let steps = 1000;
+        let dt = crate::kinematics::consts::TAU / steps as Float;
+        for (integrator, tolerance) in [(Integrator::SemiImplicitEuler, 2e-2), (Integrator::VelocityVerlet, 1e-3), (Integrator::Rk4, 1e-4)] {
+            let (mut p, mut v) = ((1.0, 0.0), (0.0, 1.0));
+            for _ in 0..steps {
+                (p, v) = integrator.step(p, v, dt, |p, _, _| (-p.0, -p.1));
+            }
+            assert!((p.0 - 1.0).abs() < tolerance && p.1.abs() < tolerance, "{:?} ended at {:?}", integrator, p);
+        }
+    }
+}
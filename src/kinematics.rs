@@ -4,6 +4,17 @@
 //~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
 
 #![allow(dead_code)]
+
+//every length, time and coefficient goes through Float, so the f64 feature switches the whole simulation to double precision
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Var {
     T,
@@ -130,11 +141,11 @@ pub trait Function {
     //todo: add resulting var?
     fn check(&self) -> Result<(),FunctionInternalError>;
     fn check_recursive(&self) -> Result<(), FunctionInternalError>;
-    fn compile(&self) -> Result<Box<dyn Fn(f32) -> Result<f32,EvalFunctionError>>, FunctionInternalError> {
+    fn compile(&self) -> Result<Box<dyn Fn(Float) -> Result<Float,EvalFunctionError>>, FunctionInternalError> {
         self.check_recursive()?;
         Ok(self.compile_unchecked())
     }
-    fn compile_unchecked(&self) -> Box<dyn Fn(f32) -> Result<f32,EvalFunctionError>>;
+    fn compile_unchecked(&self) -> Box<dyn Fn(Float) -> Result<Float,EvalFunctionError>>;
     fn check_input(&self, var : Var, units : Units) -> Result<(),FunctionCompatibilityError> {
         if self.var_units() == units {
             if self.var() == var {
@@ -147,7 +158,8 @@ pub trait Function {
         }    
     }
 
-    fn stretch_vert(&self, n : f32) -> Box<dyn Function>;
+    fn stretch_vert(&self, n : Float) -> Box<dyn Function>;
+    fn stretch_vert_units(&self, n : Float, units : Units) -> Box<dyn Function>; //stretch by a quantity with units of its own, like dividing a force by a mass
     fn stereotype() -> Self where Self : Sized; //can only be called on a variant of Function not just a dyn Function type
     //DiffrientiationBehavior
     fn differentiated(&self, respect : Var) -> Result<Box<dyn Function>, DiffrientiationError>;
@@ -155,10 +167,11 @@ pub trait Function {
     //     self = self.differentiated(respect)?;
     // }
     //IntegrationBehavior
-    fn integrated_c(&self, respect : Var, c : f32) -> Result<Box<dyn Function>, IntegrationError>;
+    fn integrated_c(&self, respect : Var, c : Float) -> Result<Box<dyn Function>, IntegrationError>;
     fn integrated(&self, respect : Var) -> Result<Box<dyn Function>, IntegrationError> {
         self.integrated_c(respect, 0.0)
     }
+    fn to_polynomial(&self) -> Option<Polynomial>; //collapses the function into one polynomial if it is exactly one, used for solving
     fn debug(&self);
 }    
 
@@ -183,13 +196,13 @@ enum KinematicsFunctions {
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Monomial {
-    pub coefficient : f32,
+    pub coefficient : Float,
     pub units_coefficient : Units,
     pub exponent : i32,
 }    
 
 impl Monomial {
-    pub fn init(coefficient : f32, units_coefficient : Units, exponent : i32) -> Self {
+    pub fn init(coefficient : Float, units_coefficient : Units, exponent : i32) -> Self {
         Monomial {
             coefficient,
             units_coefficient,
@@ -217,6 +230,151 @@ impl Polynomial {
     }    
 }    
 
+impl Polynomial {
+    pub fn from_coefficients(var : Var, var_units : Units, final_units : Units, coefficients : &[Float]) -> Self {
+        //coefficients[i] is the coefficient of var^i, units are filled in so the polynomial checks
+        let terms = coefficients.iter().enumerate()
+            .map(|(i, c)| Monomial::init(*c, final_units / var_units.pow(i as i32), i as i32))
+            .collect();
+        Polynomial::init(var, var_units, final_units, terms)
+    }
+    pub fn coefficients(&self) -> Vec<Float> {
+        //dense coefficients indexed by exponent, works on unsorted expressions too
+        let mut ret : Vec<Float> = Vec::new();
+        for monomial in &self.expression {
+            if monomial.exponent < 0 {
+                continue;
+            }
+            if ret.len() < (monomial.exponent + 1) as usize {
+                ret.resize((monomial.exponent + 1) as usize, 0.0);
+            }
+            ret[monomial.exponent as usize] += monomial.coefficient;
+        }
+        ret
+    }
+    pub fn degree(&self) -> Option<usize> {
+        //None for the zero polynomial
+        self.coefficients().iter().rposition(|c| *c != 0.0)
+    }
+    pub fn eval(&self, input : Float) -> Float {
+        self.coefficients().iter().rev().fold(0.0, |acc, c| acc * input + c)
+    }
+    pub fn shifted(&self, n : Float) -> Polynomial {
+        //p(x + n), expanding each (x + n)^k with the binomial theorem
+        let coefficients = self.coefficients();
+        let mut shifted = vec![0.0; coefficients.len()];
+        for (k, c) in coefficients.iter().enumerate() {
+            for (i, term) in shifted.iter_mut().enumerate().take(k + 1) {
+                *term += c * count_combinations(k as u64, i as u64) as Float * n.powi((k - i) as i32);
+            }
+        }
+        Polynomial::from_coefficients(self.var, self.var_units, self.final_units, &shifted)
+    }
+    fn check_compatible(&self, other : &Polynomial) -> Result<(), FunctionCompatibilityError> {
+        if self.var != other.var {
+            return Err(FunctionCompatibilityError::InvalidVar);
+        }
+        if self.var_units != other.var_units {
+            return Err(FunctionCompatibilityError::InvalidUnits);
+        }
+        Ok(())
+    }
+    pub fn sum(&self, other : &Polynomial) -> Result<Polynomial, FunctionCompatibilityError> {
+        self.check_compatible(other)?;
+        if self.final_units != other.final_units {
+            return Err(FunctionCompatibilityError::InvalidUnits);
+        }
+        let (mut a, b) = (self.coefficients(), other.coefficients());
+        if a.len() < b.len() {
+            a.resize(b.len(), 0.0);
+        }
+        for i in 0..b.len() {
+            a[i] += b[i];
+        }
+        Ok(Polynomial::from_coefficients(self.var, self.var_units, self.final_units, &a))
+    }
+    pub fn difference(&self, other : &Polynomial) -> Result<Polynomial, FunctionCompatibilityError> {
+        self.sum(&Polynomial::from_coefficients(other.var, other.var_units, other.final_units, &other.coefficients().iter().map(|c| -c).collect::<Vec<Float>>()))
+    }
+    pub fn product(&self, other : &Polynomial) -> Result<Polynomial, FunctionCompatibilityError> {
+        self.check_compatible(other)?;
+        let (a, b) = (self.coefficients(), other.coefficients());
+        if a.is_empty() || b.is_empty() {
+            return Ok(Polynomial::from_coefficients(self.var, self.var_units, self.final_units * other.final_units, &[]));
+        }
+        let mut ret = vec![0.0; a.len() + b.len() - 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                ret[i + j] += a[i] * b[j];
+            }
+        }
+        Ok(Polynomial::from_coefficients(self.var, self.var_units, self.final_units * other.final_units, &ret))
+    }
+    pub fn extremes_within(&self, lo : Float, hi : Float) -> (Float, Float) {
+        //smallest and largest values taken on [lo, hi], found at the ends or where the derivative is zero
+        let coefficients = self.coefficients();
+        let derivative : Vec<Float> = coefficients.iter().enumerate().skip(1).map(|(i, c)| c * i as Float).collect();
+        let derivative = Polynomial::from_coefficients(self.var, self.var_units, self.final_units / self.var_units, &derivative);
+        let mut ret = (self.eval(lo).min(self.eval(hi)), self.eval(lo).max(self.eval(hi)));
+        for x in derivative.roots_within(lo, hi) {
+            let y = self.eval(x);
+            ret = (ret.0.min(y), ret.1.max(y));
+        }
+        ret
+    }
+    pub fn roots_within(&self, lo : Float, hi : Float) -> Vec<Float> {
+        //real roots in [lo, hi] in ascending order
+        //roots of the derivative split the range into monotonic pieces, each piece is bisected if it changes sign
+        //roots that only touch zero without crossing (double roots) are not reported
+        let coefficients = self.coefficients();
+        let degree = match self.degree() {
+            Some(d) => d,
+            None => return Vec::new(),
+        };
+        if degree == 0 || lo > hi {
+            return Vec::new();
+        }
+        if degree == 1 {
+            let root = -coefficients[0] / coefficients[1];
+            return if root >= lo && root <= hi { vec![root] } else { Vec::new() };
+        }
+        let derivative : Vec<Float> = (1..=degree).map(|i| coefficients[i] * i as Float).collect();
+        let derivative = Polynomial::from_coefficients(self.var, self.var_units, self.final_units / self.var_units, &derivative);
+        let mut bounds = vec![lo];
+        bounds.extend(derivative.roots_within(lo, hi));
+        bounds.push(hi);
+        let mut ret : Vec<Float> = Vec::new();
+        for bound in bounds.windows(2) {
+            let (mut a, mut b) = (bound[0], bound[1]);
+            let (fa, fb) = (self.eval(a), self.eval(b));
+            let root = if fa == 0.0 {
+                a
+            } else if fb == 0.0 {
+                b
+            } else if (fa < 0.0) != (fb < 0.0) {
+                for _ in 0..64 {
+                    let mid = 0.5 * (a + b);
+                    if mid <= a || mid >= b {
+                        break;
+                    }
+                    if (self.eval(mid) < 0.0) == (fa < 0.0) {
+                        a = mid;
+                    } else {
+                        b = mid;
+                    }
+                }
+                0.5 * (a + b)
+            } else {
+                continue;
+            };
+            if ret.last() != Some(&root) {
+                ret.push(root);
+            }
+        }
+        ret
+    }
+}
+
 impl Function for Polynomial {
     fn var_units(&self) -> Units {
         self.var_units
@@ -250,15 +408,15 @@ impl Function for Polynomial {
     fn check_recursive(&self) -> Result<(), FunctionInternalError> {
         self.check()
     }
-    fn compile_unchecked(&self) -> Box<dyn Fn(f32) -> Result<f32,EvalFunctionError>> {
-        let mut evalexpr : Vec<f32> = Vec::new();
+    fn compile_unchecked(&self) -> Box<dyn Fn(Float) -> Result<Float,EvalFunctionError>> {
+        let mut evalexpr : Vec<Float> = Vec::new();
         for monomial in &self.expression {
             if evalexpr.len() < (monomial.exponent + 1) as usize {
                 evalexpr.resize((monomial.exponent + 1) as usize, 0.0);
             }    
             evalexpr[monomial.exponent as usize] = monomial.coefficient;
         }    
-        Box::new(move |input : f32| {
+        Box::new(move |input : Float| {
             let mut result = 0.0;
             for i in 0..evalexpr.len() {
                 result += evalexpr[i] * input.powi(i as i32);
@@ -266,30 +424,22 @@ impl Function for Polynomial {
             Ok(result)
         })    
     }
-    fn stretch_vert(&self, n : f32) -> Box<dyn Function> {
+    fn stretch_vert(&self, n : Float) -> Box<dyn Function> {
         let mut ret = self.clone();
         for monomial in &mut ret.expression {
             monomial.coefficient *= n;
         }    
         Box::new(ret)
     }
-    // fn shift_hor(&self, n : f32) -> Box<dyn Function> {
-    //     //shift the polynomial horizontally by n
-    //     //substitute x = x - n and each exponent for each term 
-    //     let mut new_expression : Vec<Monomial> = (0..self.expression.len()).map(|x| Monomial::init(0.0, self.final_units / self.var_units.pow(x as i32), x as i32)).collect();
-    //     for monomial in &self.expression {
-    //         let mut add_coefficients = Vec::with_capacity(self.expression.len());
-    //         for i in 0..=(monomial.exponent as usize) {
-    //             add_coefficients.push((-n).powi(monomial.exponent - i as i32) * count_combinations(monomial.exponent as u64, i as u64) as f32 * monomial.coefficient);
-    //         }
-    //         //dbg!(&add_coefficients);
-    //         for i in 0..=(monomial.exponent as usize) {
-    //             new_expression[i].coefficient += add_coefficients[i];
-    //         }
-    //         //finish this
-    //     }
-    //     Box::new(Polynomial::init(self.var, self.var_units, self.final_units, new_expression))
-    // }
+    fn stretch_vert_units(&self, n : Float, units : Units) -> Box<dyn Function> {
+        let mut ret = self.clone();
+        for monomial in &mut ret.expression {
+            monomial.coefficient *= n;
+            monomial.units_coefficient = monomial.units_coefficient * units;
+        }
+        ret.final_units = ret.final_units * units;
+        Box::new(ret)
+    }
     fn stereotype() -> Self {
         Polynomial::init(Var::X, Unit::M.units(), Unit::M.units(), vec![Monomial::init(1.0, Unit::M.units(), 1)])
     }
@@ -300,7 +450,7 @@ impl Function for Polynomial {
             let mut derivative : Vec<Monomial> = Vec::new();
             for monomial in &self.expression {
                 if monomial.exponent != 0 {
-                    derivative.push(Monomial::init(monomial.coefficient * monomial.exponent as f32, monomial.units_coefficient, monomial.exponent - 1));
+                    derivative.push(Monomial::init(monomial.coefficient * monomial.exponent as Float, monomial.units_coefficient, monomial.exponent - 1));
                 } 
             }    
             Ok(Box::new(Polynomial::init(self.var, self.var_units, self.final_units / self.var_units, derivative)))
@@ -309,12 +459,12 @@ impl Function for Polynomial {
         }    
     }
 
-    fn integrated_c(&self, respect : Var, c : f32) -> Result<Box<dyn Function>, IntegrationError> {
+    fn integrated_c(&self, respect : Var, c : Float) -> Result<Box<dyn Function>, IntegrationError> {
         if respect == self.var {
             let mut integral : Vec<Monomial> = Vec::new();
             integral.push(Monomial { coefficient: c, units_coefficient: self.final_units * self.var_units, exponent: 0 });
             for monomial in &self.expression {
-                integral.push(Monomial::init(monomial.coefficient / (monomial.exponent + 1) as f32, monomial.units_coefficient, monomial.exponent + 1));
+                integral.push(Monomial::init(monomial.coefficient / (monomial.exponent + 1) as Float, monomial.units_coefficient, monomial.exponent + 1));
             }
             Ok(Box::new(Polynomial::init(self.var, self.var_units, self.final_units * self.var_units, integral)))
         } else {
@@ -322,6 +472,10 @@ impl Function for Polynomial {
         }    
     }
 
+    fn to_polynomial(&self) -> Option<Polynomial> {
+        Some(self.clone())
+    }
+
     fn debug(&self) {
         dbg!(&self.expression);
         dbg!(self.var);
@@ -382,14 +536,14 @@ impl Function for SumFunction {
         self.f2.check()?;
         self.check()
     }
-    fn compile_unchecked(&self) -> Box<dyn Fn(f32) -> Result<f32,EvalFunctionError>> {
+    fn compile_unchecked(&self) -> Box<dyn Fn(Float) -> Result<Float,EvalFunctionError>> {
         let closure1 = self.f1.compile_unchecked();
         let closure2 = self.f2.compile_unchecked();
         Box::new(move |x| {
             Ok(closure1(x)? + closure2(x)?)
         })
     }
-    fn stretch_vert(&self, n : f32) -> Box<dyn Function> {
+    fn stretch_vert(&self, n : Float) -> Box<dyn Function> {
         Box::new(SumFunction {
             var : self.var,
             var_units : self.var_units,
@@ -398,6 +552,15 @@ impl Function for SumFunction {
             f2 : self.f2.stretch_vert(n),
         })
     }
+    fn stretch_vert_units(&self, n : Float, units : Units) -> Box<dyn Function> {
+        Box::new(SumFunction {
+            var : self.var,
+            var_units : self.var_units,
+            final_units : self.final_units * units,
+            f1 : self.f1.stretch_vert_units(n, units),
+            f2 : self.f2.stretch_vert_units(n, units),
+        })
+    }
     fn stereotype() -> Self where Self : Sized {
         SumFunction {
             f1 : Box::new(Polynomial::stereotype()),
@@ -412,7 +575,7 @@ impl Function for SumFunction {
             Ok(Box::new(SumFunction {
                 var : self.var,
                 var_units : self.var_units,
-                final_units : self.final_units,
+                final_units : self.final_units / self.var_units,
                 f1 : self.f1.differentiated(respect)?,
                 f2 : self.f2.differentiated(respect)?,
             }))
@@ -420,12 +583,12 @@ impl Function for SumFunction {
             Err(DiffrientiationError::ProhibitedRespect)
         }    
     }  
-    fn integrated_c(&self, respect : Var, c : f32) -> Result<Box<dyn Function>, IntegrationError> { //preferably the function that is easier to add c to should go in f2; use bigger/more complex function first
+    fn integrated_c(&self, respect : Var, c : Float) -> Result<Box<dyn Function>, IntegrationError> { //preferably the function that is easier to add c to should go in f2; use bigger/more complex function first
         if respect == self.var {
             Ok(Box::new(SumFunction {
                 var : self.var,
                 var_units : self.var_units,
-                final_units : self.final_units,
+                final_units : self.final_units * self.var_units,
                 f1 : self.f1.integrated(respect)?,
                 f2 : self.f2.integrated_c(respect, c)?,
             }))
@@ -434,6 +597,10 @@ impl Function for SumFunction {
         }    
     }
 
+    fn to_polynomial(&self) -> Option<Polynomial> {
+        self.f1.to_polynomial()?.sum(&self.f2.to_polynomial()?).ok()
+    }
+
     fn debug(&self) {
         self.f1.debug();
         self.f2.debug();
@@ -507,6 +674,26 @@ mod tests {
         assert_eq!(derivative.compile().unwrap()(1.0).unwrap(), 2.0);
     }
     #[test]
+    fn polynomial_shift() {
+        let meters = Unit::M.units();
+        let polynomial = Polynomial::from_coefficients(Var::X, meters, meters, &[1.0, -2.0, 3.0]);
+        let shifted = polynomial.shifted(2.0);
+        assert_eq!(shifted.eval(1.0), polynomial.eval(3.0));
+        assert_eq!(shifted.compile().unwrap()(-1.0).unwrap(), polynomial.eval(1.0));
+    }
+    #[test]
+    fn polynomial_roots() {
+        let meters = Unit::M.units();
+        //(x - 1)(x - 2)(x + 3)
+        let polynomial = Polynomial::from_coefficients(Var::X, meters, meters, &[6.0, -7.0, 0.0, 1.0]);
+        let roots = polynomial.roots_within(-10.0, 10.0);
+        assert_eq!(roots.len(), 3);
+        for (root, expected) in roots.iter().zip([-3.0, 1.0, 2.0]) {
+            assert!((root - expected).abs() < 1e-4);
+        }
+        assert_eq!(polynomial.roots_within(0.0, 1.5).len(), 1);
+    }
+    #[test]
     fn polynomial_integrate() {
         let meter = Unit::M;
         let meters = meter.units();
@@ -517,4 +704,18 @@ mod tests {
         let integral = polynomial.integrated_c(Var::X, 0.0).unwrap();
         assert_eq!(integral.compile().unwrap()(1.0).unwrap(), 4.0);
     }
+    #[test]
+    fn sum_function_units() {
+        //integrating or differentiating a sum has to carry its units along like its parts do, or it fails its own check
+        let meters = Unit::M.units();
+        let seconds = Unit::S.units();
+        let f = |c : Float| -> Box<dyn Function> { Box::new(Polynomial::from_coefficients(Var::T, seconds, meters, &[c, 1.0])) };
+        let sum = SumFunction::from_compatible(f(1.0), f(2.0)).unwrap();
+        let integral = sum.integrated_c(Var::T, 5.0).unwrap();
+        assert_eq!(integral.final_units(), meters * seconds);
+        assert_eq!(integral.compile().unwrap()(2.0).unwrap(), 5.0 + 3.0 * 2.0 + 2.0 * 2.0);
+        let derivative = sum.differentiated(Var::T).unwrap();
+        assert_eq!(derivative.final_units(), meters / seconds);
+        assert_eq!(derivative.compile().unwrap()(3.0).unwrap(), 2.0);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+//link.rs
+//springs and rigid rods joining pairs of balls, pivots pinning balls to points in the world, and ropes strung from both, ball ends are ids resolved by the space each tick
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#![allow(dead_code)]
+use super::kinematics::Float;
+use super::physics::BallId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spring {
+    pub a : BallId,
+    pub b : BallId,
+    pub rest_length : Float,
+    pub stiffness : Float, //newtons per meter of stretch
+    pub damping : Float, //newton seconds per meter, against how fast the spring is stretching
+}
+
+impl Spring {
+    pub fn new(a : BallId, b : BallId, rest_length : Float, stiffness : Float, damping : Float) -> Spring {
+        Spring { a, b, rest_length, stiffness, damping }
+    }
+    pub fn tension(&self, length : Float, rate : Float) -> Float {
+        //newtons pulling the ends together, negative when compressed springs push them apart
+        self.stiffness * (length - self.rest_length) + self.damping * rate
+    }
+    pub fn joins(&self, id : BallId) -> bool {
+        self.a == id || self.b == id
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceConstraint {
+    pub a : BallId,
+    pub b : BallId,
+    pub length : Float, //between the centers, held exactly rather than sprung back to
+    pub slack : bool, //only held to at most length, so the link pulls like a rope but never pushes
+}
+
+impl DistanceConstraint {
+    pub fn new(a : BallId, b : BallId, length : Float) -> DistanceConstraint {
+        DistanceConstraint { a, b, length, slack : false }
+    }
+    pub fn max(a : BallId, b : BallId, length : Float) -> DistanceConstraint {
+        DistanceConstraint { a, b, length, slack : true }
+    }
+    pub fn joins(&self, id : BallId) -> bool {
+        self.a == id || self.b == id
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pivot {
+    pub ball : BallId,
+    pub x : Float, //the point the ball swings about, fixed in the world
+    pub y : Float,
+    pub length : Float, //from the point to the ball's center
+    pub slack : bool, //only held to at most length, a tether rather than a rod
+}
+
+impl Pivot {
+    pub fn new(ball : BallId, x : Float, y : Float, length : Float) -> Pivot {
+        Pivot { ball, x, y, length, slack : false }
+    }
+    pub fn max(ball : BallId, x : Float, y : Float, length : Float) -> Pivot {
+        Pivot { ball, x, y, length, slack : true }
+    }
+    pub fn joins(&self, id : BallId) -> bool {
+        self.ball == id
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RopeEnd {
+    Point(Float, Float),
+    Ball(BallId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RopeError {
+    NoSegments,
+    RemovedEnd(BallId), //an end is tied to a ball that has been removed
+    TooShort(Float), //the gap between the ends, which the rope doesn't reach across
+    Crowded(Float), //how far apart the balls would start along the gap, under their diameter so neighbours would overlap
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rope {
+    pub points : Vec<RopeEnd>, //in order from one end to the other, the segment balls in between, drawn as a line through them
+    pub ends : (RopeEnd, RopeEnd), //what the rope was tied to, kept when a ball it was tied to is removed
+}
+
+impl Rope {
+    pub fn segments(&self) -> impl Iterator<Item = BallId> + '_ {
+        //the rope's own balls, leaving out any ball an end is tied to
+        self.points.iter().filter_map(move |&point| match point {
+            RopeEnd::Ball(id) if point != self.ends.0 && point != self.ends.1 => Some(id),
+            _ => None,
+        })
+    }
+    pub fn after_removal(&self, removed : BallId) -> Rope {
+        //a rope losing a ball is drawn straight across the gap, the links that held it are already gone
+        let points = self.points.iter().copied().filter(|&point| point != RopeEnd::Ball(removed)).collect();
+        Rope { points, ..*self }
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn spring_tension() {
+        let spring = Spring::new(BallId::default(), BallId::default(), 2.0, 10.0, 0.5);
+        assert_eq!(spring.tension(3.0, 0.0), 10.0);
+        assert_eq!(spring.tension(1.0, 0.0), -10.0);
+        assert_eq!(spring.tension(2.0, -4.0), -2.0);
+    }
+}
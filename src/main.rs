@@ -1,5 +1,11 @@
 mod kinematics;
 mod physics;
+mod broadphase;
+mod obstacle;
+mod nbody;
+mod integrator;
+mod link;
+mod timestep;
 mod gl;
 mod drive;
 
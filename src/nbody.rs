@@ -0,0 +1,187 @@
+//nbody.rs
+//inverse square fields between bodies, summed exactly, over chosen pairs, or approximated with a barnes hut quadtree
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#![allow(dead_code)]
+use super::kinematics::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body {
+    pub x : Float,
+    pub y : Float,
+    pub source : Float, //what the field comes from, mass for gravity or charge for electrostatics
+}
+
+//the field at body i is the sum over the others of source_j * (r_i - r_j) / |r_i - r_j|^3, pointing away from positive sources
+//softening adds to the squared distance so close passes don't blow up
+fn contribution(from : (Float, Float, Float), at : (Float, Float), softening : Float) -> (Float, Float) {
+    let (dx, dy) = (at.0 - from.0, at.1 - from.1);
+    let d2 = dx * dx + dy * dy + softening * softening;
+    if d2 == 0.0 {
+        return (0.0, 0.0);
+    }
+    let scale = from.2 / (d2 * d2.sqrt());
+    (dx * scale, dy * scale)
+}
+
+pub fn field_exact(bodies : &[Body], softening : Float) -> Vec<(Float, Float)> {
+    //every pair, O(n^2)
+    let pairs : Vec<(usize, usize)> = (0..bodies.len()).flat_map(|i| ((i + 1)..bodies.len()).map(move |j| (i, j))).collect();
+    field_pairs(bodies, &pairs, softening)
+}
+
+pub fn field_pairs(bodies : &[Body], pairs : &[(usize, usize)], softening : Float) -> Vec<(Float, Float)> {
+    //only the given pairs, each acting both ways
+    let mut field = vec![(0.0, 0.0); bodies.len()];
+    for &(i, j) in pairs {
+        let (a, b) = (bodies[i], bodies[j]);
+        let (ex, ey) = contribution((b.x, b.y, b.source), (a.x, a.y), softening);
+        field[i].0 += ex;
+        field[i].1 += ey;
+        let (ex, ey) = contribution((a.x, a.y, a.source), (b.x, b.y), softening);
+        field[j].0 += ex;
+        field[j].1 += ey;
+    }
+    field
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+const MAX_DEPTH : usize = 32; //bodies still sharing a cell this deep are close enough to just share a leaf
+
+struct Node {
+    cx : Float, //center and half width of the square the node covers
+    cy : Float,
+    half : Float,
+    source : Float, //total source, placed at (mx, my)
+    mx : Float,
+    my : Float,
+    children : Vec<usize>,
+    bodies : Vec<usize>, //only filled in leaves
+}
+
+pub struct QuadTree {
+    nodes : Vec<Node>,
+}
+
+impl QuadTree {
+    pub fn build(bodies : &[Body]) -> QuadTree {
+        let mut tree = QuadTree { nodes : Vec::new() };
+        if bodies.is_empty() {
+            return tree;
+        }
+        let (mut x1, mut y1, mut x2, mut y2) = (Float::INFINITY, Float::INFINITY, Float::NEG_INFINITY, Float::NEG_INFINITY);
+        for body in bodies {
+            (x1, y1, x2, y2) = (x1.min(body.x), y1.min(body.y), x2.max(body.x), y2.max(body.y));
+        }
+        let half = ((x2 - x1).max(y2 - y1) / 2.0).max(Float::EPSILON) * 1.001; //a little slack so bodies on the far edges land inside
+        tree.insert(bodies, (0..bodies.len()).collect(), ((x1 + x2) / 2.0, (y1 + y2) / 2.0), half, 0);
+        tree
+    }
+    fn insert(&mut self, bodies : &[Body], members : Vec<usize>, (cx, cy) : (Float, Float), half : Float, depth : usize) -> usize {
+        //the center a node's source sits at is weighted by the magnitude of each source, so opposite charges still have a sensible center
+        let weight : Float = members.iter().map(|&i| bodies[i].source.abs()).sum();
+        let source : Float = members.iter().map(|&i| bodies[i].source).sum();
+        let (mx, my) = if weight > 0.0 {
+            (members.iter().map(|&i| bodies[i].x * bodies[i].source.abs()).sum::<Float>() / weight, members.iter().map(|&i| bodies[i].y * bodies[i].source.abs()).sum::<Float>() / weight)
+        } else {
+            (cx, cy)
+        };
+        let index = self.nodes.len();
+        self.nodes.push(Node { cx, cy, half, source, mx, my, children : Vec::new(), bodies : Vec::new() });
+        if members.len() == 1 || depth >= MAX_DEPTH {
+            self.nodes[index].bodies = members;
+            return index;
+        }
+        let mut quadrants : [Vec<usize>; 4] = Default::default();
+        for i in members {
+            let quadrant = (bodies[i].x >= cx) as usize + 2 * (bodies[i].y >= cy) as usize;
+            quadrants[quadrant].push(i);
+        }
+        for (quadrant, members) in quadrants.into_iter().enumerate() {
+            if members.is_empty() {
+                continue;
+            }
+            let quarter = half / 2.0;
+            let center = (cx + if quadrant & 1 == 1 { quarter } else { -quarter }, cy + if quadrant & 2 == 2 { quarter } else { -quarter });
+            let child = self.insert(bodies, members, center, quarter, depth + 1);
+            self.nodes[index].children.push(child);
+        }
+        index
+    }
+    pub fn field_at(&self, bodies : &[Body], i : usize, theta : Float, softening : Float) -> (Float, Float) {
+        //a node far enough away that its width over its distance is under theta acts as one body
+        //nodes containing body i are always opened, so it never feels itself
+        let (x, y) = (bodies[i].x, bodies[i].y);
+        let mut field = (0.0, 0.0);
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let contains = (x - node.cx).abs() <= node.half && (y - node.cy).abs() <= node.half;
+            let distance = (x - node.mx).hypot(y - node.my);
+            let (ex, ey) = if !node.bodies.is_empty() {
+                node.bodies.iter().filter(|&&j| j != i).fold((0.0, 0.0), |(ex, ey), &j| {
+                    let (dx, dy) = contribution((bodies[j].x, bodies[j].y, bodies[j].source), (x, y), softening);
+                    (ex + dx, ey + dy)
+                })
+            } else if !contains && 2.0 * node.half < theta * distance {
+                contribution((node.mx, node.my, node.source), (x, y), softening)
+            } else {
+                stack.extend(&node.children);
+                continue;
+            };
+            field = (field.0 + ex, field.1 + ey);
+        }
+        field
+    }
+}
+
+pub fn field_barnes_hut(bodies : &[Body], theta : Float, softening : Float) -> Vec<(Float, Float)> {
+    //O(n log n), theta of 0 opens every node and matches the exact sum
+    let tree = QuadTree::build(bodies);
+    (0..bodies.len()).map(|i| tree.field_at(bodies, i, theta, softening)).collect()
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn scattered_bodies(n : usize, seed : u64, spread : Float) -> Vec<Body> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 40) as Float / (1u64 << 24) as Float
+        };
+        (0..n).map(|_| Body { x : (next() - 0.5) * spread, y : (next() - 0.5) * spread, source : next() + 0.1 }).collect()
+    }
+    #[test]
+    fn two_bodies() {
+        let bodies = [Body { x : 0.0, y : 0.0, source : 2.0 }, Body { x : 3.0, y : 4.0, source : 5.0 }];
+        let field = field_exact(&bodies, 0.0);
+        //|d| = 5, so each feels the other's source over 25 along the line between them
+        assert!((field[0].0 - -5.0 / 25.0 * 0.6).abs() < 1e-6);
+        assert!((field[0].1 - -5.0 / 25.0 * 0.8).abs() < 1e-6);
+        assert!((field[1].0 - 2.0 / 25.0 * 0.6).abs() < 1e-6);
+        assert!((field[1].1 - 2.0 / 25.0 * 0.8).abs() < 1e-6);
+    }
+    #[test]
+    fn barnes_hut_approximates_exact() {
+        let bodies = scattered_bodies(400, 7, 100.0);
+        let exact = field_exact(&bodies, 0.1);
+        for (theta, tolerance) in [(0.0, 1e-3), (0.5, 5e-2)] {
+            let approx = field_barnes_hut(&bodies, theta, 0.1);
+            let mut error = 0.0;
+            let mut total = 0.0;
+            for (e, a) in exact.iter().zip(&approx) {
+                error += (e.0 - a.0).hypot(e.1 - a.1);
+                total += e.0.hypot(e.1);
+            }
+            assert!(error / total < tolerance, "theta {} off by {}", theta, error / total);
+        }
+        //coincident bodies share a leaf instead of recursing forever
+        let stacked = vec![Body { x : 1.0, y : 1.0, source : 1.0 }; 3];
+        assert_eq!(field_barnes_hut(&stacked, 0.5, 0.0), vec![(0.0, 0.0); 3]);
+    }
+}
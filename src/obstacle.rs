@@ -0,0 +1,145 @@
+//obstacle.rs
+//static geometry balls collide with besides the walls, floor and ceiling of the space
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#![allow(dead_code)]
+use super::kinematics::Float;
+use super::kinematics::consts;
+use super::broadphase::Aabb;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub x1 : Float,
+    pub y1 : Float,
+    pub x2 : Float,
+    pub y2 : Float,
+}
+
+impl Segment {
+    pub fn new(x1 : Float, y1 : Float, x2 : Float, y2 : Float) -> Segment {
+        Segment { x1, y1, x2, y2 }
+    }
+    pub fn length(&self) -> Float {
+        (self.x2 - self.x1).hypot(self.y2 - self.y1)
+    }
+    pub fn direction(&self) -> (Float, Float) {
+        //unit vector from the first end to the second, zero for a segment that is a single point
+        let length = self.length();
+        if length == 0.0 {
+            return (0.0, 0.0);
+        }
+        ((self.x2 - self.x1) / length, (self.y2 - self.y1) / length)
+    }
+    pub fn closest_point(&self, x : Float, y : Float) -> (Float, Float) {
+        let (dx, dy) = self.direction();
+        let along = ((x - self.x1) * dx + (y - self.y1) * dy).clamp(0.0, self.length());
+        (self.x1 + dx * along, self.y1 + dy * along)
+    }
+    pub fn bounds(&self) -> Aabb {
+        Aabb { x1 : self.x1.min(self.x2), y1 : self.y1.min(self.y2), x2 : self.x1.max(self.x2), y2 : self.y1.max(self.y2) }
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PolygonError {
+    TooFewVertices,
+    NotConvex,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices : Vec<(Float, Float)>, //in order around the outline, either winding
+}
+
+impl Polygon {
+    pub fn new(vertices : Vec<(Float, Float)>) -> Result<Polygon, PolygonError> {
+        if vertices.len() < 3 {
+            return Err(PolygonError::TooFewVertices);
+        }
+        //convex when every corner turns the same way, collinear corners don't turn at all
+        let n = vertices.len();
+        let mut winding = 0.0 as Float;
+        for k in 0..n {
+            let ((ax, ay), (bx, by), (cx, cy)) = (vertices[k], vertices[(k + 1) % n], vertices[(k + 2) % n]);
+            let turn = (bx - ax) * (cy - by) - (by - ay) * (cx - bx);
+            if turn * winding < 0.0 {
+                return Err(PolygonError::NotConvex);
+            }
+            if turn != 0.0 {
+                winding = turn;
+            }
+        }
+        if winding == 0.0 {
+            return Err(PolygonError::TooFewVertices); //every vertex on one line
+        }
+        Ok(Polygon { vertices })
+    }
+    pub fn regular(x : Float, y : Float, r : Float, sides : usize) -> Polygon {
+        //a regular polygon with vertices r from (x, y), the first pointing straight up, handy for pegs
+        let sides = sides.max(3);
+        let vertices = (0..sides).map(|k| {
+            let angle = consts::FRAC_PI_2 + consts::TAU * k as Float / sides as Float;
+            (x + r * angle.cos(), y + r * angle.sin())
+        }).collect();
+        Polygon { vertices }
+    }
+    pub fn vertices(&self) -> &[(Float, Float)] {
+        &self.vertices
+    }
+    pub fn edges(&self) -> Vec<Segment> {
+        let n = self.vertices.len();
+        (0..n).map(|k| {
+            let ((x1, y1), (x2, y2)) = (self.vertices[k], self.vertices[(k + 1) % n]);
+            Segment::new(x1, y1, x2, y2)
+        }).collect()
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Obstacle {
+    Segment(Segment),
+    Polygon(Polygon),
+}
+
+impl Obstacle {
+    pub fn edges(&self) -> Vec<Segment> {
+        //balls collide with obstacles edge by edge, corners are covered by the ends of the edges
+        match self {
+            Obstacle::Segment(segment) => vec![*segment],
+            Obstacle::Polygon(polygon) => polygon.edges(),
+        }
+    }
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn segment_closest_point() {
+        let segment = Segment::new(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(segment.closest_point(1.0, 3.0), (1.0, 0.0));
+        assert_eq!(segment.closest_point(-2.0, 1.0), (0.0, 0.0));
+        assert_eq!(segment.closest_point(7.0, -1.0), (4.0, 0.0));
+        assert_eq!(Segment::new(1.0, 1.0, 1.0, 1.0).closest_point(5.0, 5.0), (1.0, 1.0));
+    }
+    #[test]
+    fn polygon_convexity() {
+        assert_eq!(Polygon::new(vec![(0.0, 0.0), (1.0, 0.0)]), Err(PolygonError::TooFewVertices));
+        assert_eq!(Polygon::new(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]), Err(PolygonError::TooFewVertices));
+        assert_eq!(Polygon::new(vec![(0.0, 0.0), (2.0, 0.0), (1.0, 0.5), (1.0, 2.0)]), Err(PolygonError::NotConvex));
+        //both windings, and a collinear vertex along an edge
+        let square = vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        assert!(Polygon::new(square.clone()).is_ok());
+        assert!(Polygon::new(square.into_iter().rev().collect()).is_ok());
+        let peg = Polygon::regular(0.0, 0.0, 1.0, 6);
+        assert_eq!(peg.edges().len(), 6);
+        assert!(Polygon::new(peg.vertices().to_vec()).is_ok());
+    }
+}
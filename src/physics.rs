@@ -1,17 +1,40 @@
 #![allow(dead_code)]
 use super::kinematics;
+use kinematics::Float;
 use kinematics::Function;
+use kinematics::Polynomial;
 use kinematics::SumFunction;
 use kinematics::Unit;
 use kinematics::Units;
 use kinematics::Var;
 use kinematics::EvalFunctionError;
-pub const GRAVITY_MPS2: f32 = -100.81;
+use super::broadphase;
+use broadphase::Aabb;
+use broadphase::BroadPhase;
+use super::obstacle;
+use obstacle::Obstacle;
+use obstacle::Segment;
+use super::nbody;
+use super::integrator;
+use super::link;
+use link::Spring;
+use link::DistanceConstraint;
+use link::Pivot;
+use link::Rope;
+use link::RopeEnd;
+use link::RopeError;
+use integrator::Integrator;
+pub const GRAVITY_MPS2: Float = -100.81;
 pub const DBG_INFO_ONE_COLLISIONS: bool = false;
 pub const DBG_INFO_TWO_COLLISIONS: bool = true;
+pub const DEFAULT_STATIC_FRICTION: Float = 0.6;
+pub const DEFAULT_KINETIC_FRICTION: Float = 0.4;
+pub const CONTACT_TOLERANCE: Float = 1e-4; //relative gap under which balls count as touching when gathering the contacts an impact spreads through
+pub const TAUT_TOLERANCE: Float = 1e-4; //relative, a slack link this close to its length is pulled tight
+pub const CLOSING_SPEED_EPSILON: Float = 1e-3; //contacts approaching slower than this aren't impacts, so settled contacts aren't solved again from rounding error
 
 struct FunctionCache {
-    pub closure: Box<dyn Fn(f32) -> Result<f32, EvalFunctionError>>,
+    pub closure: Box<dyn Fn(Float) -> Result<Float, EvalFunctionError>>,
 }
 
 impl Default for FunctionCache {
@@ -23,15 +46,51 @@ impl Default for FunctionCache {
 }
 
 impl FunctionCache {
-    pub fn new(closure: Box<dyn Fn(f32) -> Result<f32, EvalFunctionError>>) -> Self {
+    pub fn new(closure: Box<dyn Fn(Float) -> Result<Float, EvalFunctionError>>) -> Self {
         Self {
             closure,
         }
     }
 }
 
+#[derive(Default)]
+struct AccelerationCache {
+    ax: FunctionCache, //an AccelxyFunction flattened into one compiled closure per axis
+    ay: FunctionCache,
+}
+
+impl AccelerationCache {
+    fn new(a : &AccelxyFunction) -> Self {
+        use AccelxyFunction::*;
+        match a {
+            ParterFunctionVector(h, d) => {
+                let ((cx, cy), (hx, hy)) = (d.xy_h(1.0), (h.compile_unchecked(), h.compile_unchecked()));
+                Self {
+                    ax: FunctionCache::new(Box::new(move |t| hx(t).map(|h| h * cx))),
+                    ay: FunctionCache::new(Box::new(move |t| hy(t).map(|h| h * cy))),
+                }
+            }
+            IndependentFunctions(ax, ay) => Self {
+                ax: FunctionCache::new(ax.compile_unchecked()),
+                ay: FunctionCache::new(ay.compile_unchecked()),
+            },
+            CompositeAcceleration(a1, a2) => {
+                let (Self { ax: ax1, ay: ay1 }, Self { ax: ax2, ay: ay2 }) = (Self::new(a1), Self::new(a2));
+                Self {
+                    ax: FunctionCache::new(Box::new(move |t| Ok((ax1.closure)(t).unwrap_or(0.0) + (ax2.closure)(t).unwrap_or(0.0)))),
+                    ay: FunctionCache::new(Box::new(move |t| Ok((ay1.closure)(t).unwrap_or(0.0) + (ay2.closure)(t).unwrap_or(0.0)))),
+                }
+            }
+        }
+    }
+    fn at(&self, t : Float) -> (Float, Float) {
+        //x and y acceleration t seconds after the reference time it is integrated from
+        ((self.ax.closure)(t).unwrap_or(0.0), (self.ay.closure)(t).unwrap_or(0.0))
+    }
+}
+
 pub enum MaybeNew {
-    Update(f32),
+    Update(Float),
     NoUpdate,
 }
 
@@ -40,17 +99,17 @@ pub struct Recalculate {
 }
 
 impl Recalculate {
-    pub fn x(t : f32) -> Self {
+    pub fn x(t : Float) -> Self {
         Self {
             val : (MaybeNew::Update(t), MaybeNew::NoUpdate),
         }
     }
-    pub fn y(t : f32) -> Self {
+    pub fn y(t : Float) -> Self {
         Self {
             val : (MaybeNew::NoUpdate, MaybeNew::Update(t)),
         }
     }
-    pub fn xy(tx : f32, ty : f32) -> Self {
+    pub fn xy(tx : Float, ty : Float) -> Self {
         Self {
             val : (MaybeNew::Update(tx), MaybeNew::Update(ty)),
         }
@@ -62,46 +121,196 @@ impl Recalculate {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BallId {
+    slot : usize,
+    generation : u32, //bumped each time the slot's ball is removed, so handles to it stop resolving
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BallSlot {
+    generation : u32,
+    index : Option<usize>, //where the ball is in Space::balls, none while the slot is free
+}
+
 #[derive(Default)]
 pub struct Ball {
-    x: f32,
-    y: f32,
-    radius: f32,
-    mass: f32,
-    ground_bounce: f32,
-    free_bounce: f32,
+    id : BallId,
+    x: Float,
+    y: Float,
+    radius: Float,
+    mass: Float,
+    ground_bounce: Float,
+    free_bounce: Float,
     fx: FunctionCache, //respect to time
     fy: FunctionCache,
+    fvx: FunctionCache, //derivatives of fx and fy, compiled alongside them so velocities aren't differentiated on every lookup
+    fvy: FunctionCache,
+    fax: FunctionCache,
+    fay: FunctionCache,
     cached_x_dyn_function : Option<Box<dyn Function>>,
     cached_y_dyn_function : Option<Box<dyn Function>>,
-    x_reftime : f32,
-    y_reftime : f32,
+    x_reftime : Float,
+    y_reftime : Float,
     color : [f32; 4],
+    rest_x : Option<Boundary>, //boundary the ball is settled against, its path on that axis is held still
+    rest_y : Option<Boundary>,
+    shape : InertiaShape,
+    static_friction : Float, //coulomb coefficients, the tangential impulse is capped at the coefficient times the normal impulse
+    kinetic_friction : Float,
+    angle : Float, //radians counterclockwise, spin is torque free between contacts so it only needs a reference angle and rate
+    angular_velocity : Float,
+    spin_refangle : Float,
+    spin_reftime : Float,
+    force : Option<AccelxyFunction>, //newtons on this ball alone on top of the space's acceleration, timed from each recalculation the same way
+    fforce : Option<AccelerationCache>, //force compiled when it is set
+    charge : Float, //coulombs, only felt when the space's pair force is electrostatic
+    linear_drag : Float, //drag force is -(linear + quadratic * speed) * velocity, in N s/m and N s^2/m^2
+    quadratic_drag : Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InertiaShape {
+    #[default]
+    SolidSphere,
+    HollowSphere,
+    SolidDisc,
+    HollowDisc, //a thin ring
+}
+
+impl InertiaShape {
+    pub fn coefficient(&self) -> Float {
+        //k in I = k * m * r^2
+        match self {
+            InertiaShape::SolidSphere => 2.0 / 5.0,
+            InertiaShape::HollowSphere => 2.0 / 3.0,
+            InertiaShape::SolidDisc => 1.0 / 2.0,
+            InertiaShape::HollowDisc => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    Left,
+    Right,
+    Floor,
+    Ceiling,
+}
+
+impl Boundary {
+    pub const ALL : [Boundary; 4] = [Boundary::Left, Boundary::Right, Boundary::Floor, Boundary::Ceiling];
+    fn normal(&self) -> (Float, Float) {
+        //unit normal pointing from the boundary into the space
+        match self {
+            Boundary::Left => (1.0, 0.0),
+            Boundary::Right => (-1.0, 0.0),
+            Boundary::Floor => (0.0, 1.0),
+            Boundary::Ceiling => (0.0, -1.0),
+        }
+    }
+    fn pressed_by(&self, x : Float, y : Float) -> bool {
+        //whether a velocity or acceleration (x, y) pushes into the boundary, or at least not away from it
+        match self {
+            Boundary::Left => x <= 0.0,
+            Boundary::Right => x >= 0.0,
+            Boundary::Floor => y <= 0.0,
+            Boundary::Ceiling => y >= 0.0,
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundaryMode {
+    #[default]
+    Reflect, //bounces with the ball's ground bounce coefficient
+    Absorb, //deletes balls that touch it
+    Wrap, //balls whose center crosses it come back in through the opposite side with the same velocity
+    Open, //no boundary at all, balls leave the space for good
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PairForce {
+    #[default]
+    Off,
+    Gravity(Float), //newtonian attraction between masses, holding the gravitational constant
+    Coulomb(Float), //like charges repel and opposite ones attract, holding the coulomb constant
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PairForceMethod {
+    #[default]
+    Exact, //every pair
+    Cutoff(Float), //only pairs closer than this, found with the space's broad phase
+    BarnesHut(Float), //quadtree approximation, holding the opening angle theta, 0.5 is a good tradeoff
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SubstepStats {
+    pub ticks : u64,
+    pub substeps : u64, //across every tick, substeps / ticks is the average split
+    pub capped : u64, //ticks that wanted more substeps than max_substeps allowed
+    pub most : usize, //largest split of a single tick
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Impact {
+    Boundary(usize, Boundary),
+    Pair(usize, usize),
+    Edge(usize, usize), //ball, index into Space::obstacle_edges
+}
+
+pub type AccelerationField = Box<dyn Fn(Float, Float, Float, Float, Float) -> (Float, Float)>;
+
 pub struct Angle {
-    deg : f32,
+    deg : Float,
 }
 
 pub struct Space {
     time_units : Units,
     space_units : Units,
     mass_units : Units,
-    pub x1: f32,
-    pub y1: f32,
-    pub x2: f32,
-    pub y2: f32,
-    pub floor: f32,
+    pub x1: Float,
+    pub y1: Float,
+    pub x2: Float,
+    pub y2: Float,
+    pub floor: Float,
     a : AccelxyFunction,
-    elapsed: f32,
-    pub balls: Vec<Ball>,
+    fa : AccelerationCache, //a compiled once, it is looked up for every ball every tick
+    elapsed: Float,
+    balls: Vec<Ball>, //only added to and removed from through the space, so slots stays in step with it
+    slots : Vec<BallSlot>, //indexed by BallId::slot
+    free_slots : Vec<usize>,
+    pub continuous : bool, //solve for the exact time of impact within a tick instead of only checking overlap at the end
+    pub max_impacts_per_tick : usize,
+    pub broad_phase : Box<dyn BroadPhase>,
+    pub resting_threshold : Float, //normal speed below which contacts settle instead of bouncing, should exceed the speed gained from acceleration in one tick
+    pub position_correction : Float, //fraction of each overlap pushed apart per correction pass, 0 turns correction off
+    pub penetration_slop : Float, //overlap left alone so touching balls don't jitter in and out of contact
+    pub position_iterations : usize,
+    pub velocity_iterations : usize, //passes over a group of touching balls when one of them is hit, so impulses can travel through the group
+    boundary_modes : [BoundaryMode; 4], //indexed by Boundary
+    pub obstacles : Vec<Obstacle>,
+    pub springs : Vec<Spring>,
+    pub constraints : Vec<DistanceConstraint>,
+    pub pivots : Vec<Pivot>,
+    pub constraint_iterations : usize, //passes over the rods and pivots each tick, long chains need many to stay close to length
+    pub ropes : Vec<Rope>, //only what to draw, the rope balls and their links live with the others
+    pub pair_force : PairForce,
+    pub pair_force_method : PairForceMethod,
+    pub softening : Float, //added in quadrature to distances between balls so close passes don't fling them apart
+    pub integrator : Integrator,
+    pub acceleration_field : Option<AccelerationField>, //(x, y, vx, vy, elapsed) to extra acceleration, only numerical integrators can follow it
+    pub adaptive_substeps : Option<Float>, //splits each tick so no ball travels more than this fraction of its radius per substep
+    pub max_substeps : usize,
+    substep_stats : SubstepStats,
 }
 
 impl Angle {
-    fn new(deg : f32) -> Angle {
+    pub fn new(deg : Float) -> Angle {
         Angle { deg }
     }
-    fn xy_h(&self, h : f32) -> (f32, f32) {
+    fn xy_h(&self, h : Float) -> (Float, Float) {
         let rad = self.deg.to_radians();
         (rad.cos() * h, rad.sin() * h)
     }
@@ -113,127 +322,381 @@ pub enum AccelxyFunction {
     CompositeAcceleration(Box<AccelxyFunction>, Box<AccelxyFunction>),
 }
 
+impl AccelxyFunction {
+    fn units(&self) -> Option<(Var, Units, Units)> {
+        //the input variable, its units and the output units every part shares, none if the parts can't be summed
+        use AccelxyFunction::*;
+        let leaf = |f : &dyn Function| (f.var(), f.var_units(), f.final_units());
+        match self {
+            ParterFunctionVector(a, _) => Some(leaf(a.as_ref())),
+            IndependentFunctions(ax, ay) => Some(leaf(ax.as_ref())).filter(|&units| units == leaf(ay.as_ref())),
+            CompositeAcceleration(a1, a2) => a1.units().filter(|&units| a2.units() == Some(units)),
+        }
+    }
+}
+
 impl Ball {
+    fn derivatives(f : &dyn Function) -> (Box<dyn Function>, Box<dyn Function>) {
+        let v = f.differentiated(Var::T).expect("Differentiation Error");
+        let a = v.differentiated(Var::T).expect("Differentiation Error");
+        (v, a)
+    }
     pub fn soft_update_unchecked(&mut self) { 
         //use when data hasnt been recently injected and checking isn't worth
         //not checking won't result in unsafe code but could create odd function behavior
-        self.fx = FunctionCache::new(self.cached_x_dyn_function.as_ref().expect("No cache, unable to soft update!").compile_unchecked());
-        self.fy = FunctionCache::new(self.cached_y_dyn_function.as_ref().expect("No cache, unable to soft update!").compile_unchecked());
+        let x = self.cached_x_dyn_function.as_ref().expect("No cache, unable to soft update!");
+        let y = self.cached_y_dyn_function.as_ref().expect("No cache, unable to soft update!");
+        let ((vx, ax), (vy, ay)) = (Self::derivatives(x.as_ref()), Self::derivatives(y.as_ref()));
+        self.fx = FunctionCache::new(x.compile_unchecked());
+        self.fy = FunctionCache::new(y.compile_unchecked());
+        self.fvx = FunctionCache::new(vx.compile_unchecked());
+        self.fvy = FunctionCache::new(vy.compile_unchecked());
+        self.fax = FunctionCache::new(ax.compile_unchecked());
+        self.fay = FunctionCache::new(ay.compile_unchecked());
     }
     pub fn soft_update(&mut self) -> Result<(), kinematics::FunctionInternalError> {
-        let fcx = self.cached_x_dyn_function.as_ref().expect("No cache, unable to soft update!").compile()?;
-        let fcy = self.cached_y_dyn_function.as_ref().expect("No cache, unable to soft update!").compile()?;
+        let x = self.cached_x_dyn_function.as_ref().expect("No cache, unable to soft update!");
+        let y = self.cached_y_dyn_function.as_ref().expect("No cache, unable to soft update!");
+        let ((vx, ax), (vy, ay)) = (Self::derivatives(x.as_ref()), Self::derivatives(y.as_ref()));
+        let (fcx, fcy) = (x.compile()?, y.compile()?);
+        let (fcvx, fcvy, fcax, fcay) = (vx.compile()?, vy.compile()?, ax.compile()?, ay.compile()?);
         self.fx = FunctionCache::new(fcx);
         self.fy = FunctionCache::new(fcy);
+        self.fvx = FunctionCache::new(fcvx);
+        self.fvy = FunctionCache::new(fcvy);
+        self.fax = FunctionCache::new(fcax);
+        self.fay = FunctionCache::new(fcay);
         Ok(())
     }
     fn recurhelper_hard_update_unchecked(&self, a_ref : &AccelxyFunction) -> (Box<dyn Function>, Box<dyn Function>) {
-        if let AccelxyFunction::ParterFunctionVector(a, d) = a_ref {
-            let cached_d_dyn_function = a .integrated(Var::S).unwrap().integrated(Var::S).unwrap();
-            return (
-                cached_d_dyn_function.stretch_vert(d.xy_h(1.).0),
-                cached_d_dyn_function.stretch_vert(d.xy_h(1.).1)
-            );
-        }
-        else if let AccelxyFunction::IndependentFunctions(ax, ay) = a_ref {
-            return (
-                ax.integrated(Var::X).unwrap().integrated(Var::X).unwrap(),
-                ay.integrated(Var::Y).unwrap().integrated(Var::Y).unwrap()
-            );
-        }
-        else if let AccelxyFunction::CompositeAcceleration(a1, a2) = a_ref {
-            let xy1 = self.recurhelper_hard_update_unchecked(&a1);
-            let xy2 = self.recurhelper_hard_update_unchecked(&a2);
-            (
-                Box::new(SumFunction::from_compatible(xy1.0,xy2.0).unwrap()),
-                Box::new(SumFunction::from_compatible(xy1.1,xy2.1).unwrap()),
-            )
-        }
-        else {
-            panic!("Unknown AccelxyFunction variant!");
-        }
-    }
-    pub fn hard_update_unchecked(&mut self, a_ref : &AccelxyFunction, xi : f32, yi : f32, vxi : f32, vyi : f32, t : Recalculate) {
-        use AccelxyFunction::*;
+        //flattens the acceleration tree into one x and one y acceleration, left unintegrated so the caller adds the starting velocity and position once
         match a_ref {
-            ParterFunctionVector(a, d) => {
-                todo!();
-                let cached_d_dyn_function = a .integrated(Var::X).unwrap().integrated(Var::Y).unwrap();
-                self.cached_x_dyn_function = Some(cached_d_dyn_function.stretch_vert(d.xy_h(1.0).0));
-                self.cached_y_dyn_function = Some(cached_d_dyn_function.stretch_vert(d.xy_h(1.0).1));
-                self.soft_update_unchecked();
-            }
-            IndependentFunctions(ax, ay) => {
-                if let MaybeNew::Update(x) = t.val.0 {
-                    self.x_reftime = x; 
-                    self.cached_x_dyn_function = Some(ax.integrated_c(Var::T, vxi).expect("Integration Error").integrated_c(Var::T, xi).expect("Integration Error"));
-                }
-                if let MaybeNew::Update(y) = t.val.1 {
-                    self.y_reftime = y; 
-                    self.cached_y_dyn_function = Some(ay.integrated_c(Var::T, vyi).expect("Integration Error").integrated_c(Var::T, yi).expect("Integration Error"));
-                }
-                self.soft_update_unchecked();
-            }
-            CompositeAcceleration(a1, a2) => {
-                todo!();
-                //we have two accelerations and need to integrated each parts individually, both xnet and ynet
-                //we calculate x and y from a1 and x and y from a2 recursively then throw them into a self.cached_x_dyn_function and self.cached_y_dyn_function
-
-                //todo: initial velocities work here
+            AccelxyFunction::ParterFunctionVector(a, d) => (a.stretch_vert(d.xy_h(1.0).0), a.stretch_vert(d.xy_h(1.0).1)),
+            AccelxyFunction::IndependentFunctions(ax, ay) => (ax.stretch_vert(1.0), ay.stretch_vert(1.0)),
+            AccelxyFunction::CompositeAcceleration(a1, a2) => {
                 let xy1 = self.recurhelper_hard_update_unchecked(a1);
                 let xy2 = self.recurhelper_hard_update_unchecked(a2);
-                self.cached_x_dyn_function = Some(Box::new(SumFunction::from_compatible(xy1.0,xy2.0).unwrap()));
-                self.cached_y_dyn_function = Some(Box::new(SumFunction::from_compatible(xy1.1,xy2.1).unwrap()));
-                self.soft_update_unchecked();
+                (
+                    Box::new(SumFunction::from_compatible(xy1.0, xy2.0).expect("Composite accelerations must share units")),
+                    Box::new(SumFunction::from_compatible(xy1.1, xy2.1).expect("Composite accelerations must share units")),
+                )
             }
         }
     }
-    pub fn hard_update(&mut self, a_ref : &AccelxyFunction, xi : f32, yi : f32, vxi : f32, vyi : f32, t : Recalculate) {
+    pub fn hard_update_unchecked(&mut self, a_ref : &AccelxyFunction, xi : Float, yi : Float, vxi : Float, vyi : Float, t : Recalculate) {
+        //a recalculated axis follows the acceleration again, so it no longer rests
+        if let MaybeNew::Update(_) = t.val.0 {
+            self.rest_x = None;
+        }
+        if let MaybeNew::Update(_) = t.val.1 {
+            self.rest_y = None;
+        }
+        //the accelerations are summed before integrating, so the integration constants only enter once
+        let (mut ax, mut ay) = self.recurhelper_hard_update_unchecked(a_ref);
+        if let Some(force) = &self.force {
+            let (fx, fy) = self.recurhelper_hard_update_unchecked(force);
+            let per_kg = Unit::KG.units().pow(-1);
+            ax = Box::new(SumFunction::from_compatible(ax, fx.stretch_vert_units(1.0 / self.mass, per_kg)).expect("Forces must be in newtons"));
+            ay = Box::new(SumFunction::from_compatible(ay, fy.stretch_vert_units(1.0 / self.mass, per_kg)).expect("Forces must be in newtons"));
+        }
+        if let MaybeNew::Update(x) = t.val.0 {
+            self.x_reftime = x;
+            self.cached_x_dyn_function = Some(ax.integrated_c(Var::T, vxi).expect("Integration Error").integrated_c(Var::T, xi).expect("Integration Error"));
+        }
+        if let MaybeNew::Update(y) = t.val.1 {
+            self.y_reftime = y;
+            self.cached_y_dyn_function = Some(ay.integrated_c(Var::T, vyi).expect("Integration Error").integrated_c(Var::T, yi).expect("Integration Error"));
+        }
+        self.soft_update_unchecked();
+    }
+    pub fn hard_update(&mut self, a_ref : &AccelxyFunction, xi : Float, yi : Float, vxi : Float, vyi : Float, t : Recalculate) {
         //todo: make this checked, finish hard_update_unchecked, and start to prefer the checked versions
         self.hard_update_unchecked(a_ref, xi, yi, vxi, vyi, t);
     } 
-    pub fn get_x(&self) -> f32 {
+    pub fn get_id(&self) -> BallId {
+        self.id
+    }
+    pub fn get_x(&self) -> Float {
         self.x
     }
-    pub fn get_y(&self) -> f32 {
+    pub fn get_y(&self) -> Float {
         self.y
     }
-    pub fn get_radius(&self) -> f32 {
+    pub fn get_radius(&self) -> Float {
         self.radius
     }
-    pub fn get_mass(&self) -> f32 {
+    pub fn get_mass(&self) -> Float {
         self.mass
     }
-    pub fn get_ground_bounce(&self) -> f32 {
+    pub fn get_ground_bounce(&self) -> Float {
         self.ground_bounce
     }
-    pub fn get_free_bounce(&self) -> f32 {
+    pub fn get_free_bounce(&self) -> Float {
         self.free_bounce
     }
     pub fn get_color(&self) -> [f32; 4] {
         self.color
     }
-    pub fn get_vx(&self, t : f32) -> f32 {
-        self.cached_x_dyn_function.as_ref()
-            .expect("No cache, unable to get vx!")
-            .differentiated(Var::T)
-            .expect("Differentiation Error")
-            .compile()
-            .expect("Compilation Error")
-            (t - self.x_reftime)
-            .expect("Evaluation Error")
+    pub fn get_force(&self) -> Option<&AccelxyFunction> {
+        self.force.as_ref()
+    }
+    fn acceleration_at(&self, a_ref : &AccelerationCache, t : Float) -> (Float, Float) {
+        //the space's acceleration plus this ball's own force over its mass, t seconds after the reference time
+        let (ax, ay) = a_ref.at(t);
+        match &self.fforce {
+            Some(force) => {
+                let (fx, fy) = force.at(t);
+                (ax + fx / self.mass, ay + fy / self.mass)
+            }
+            None => (ax, ay),
+        }
+    }
+    pub fn get_shape(&self) -> InertiaShape {
+        self.shape
+    }
+    pub fn set_shape(&mut self, shape : InertiaShape) {
+        self.shape = shape;
+    }
+    pub fn get_moment_of_inertia(&self) -> Float {
+        self.shape.coefficient() * self.mass * self.radius * self.radius
+    }
+    pub fn get_static_friction(&self) -> Float {
+        self.static_friction
+    }
+    pub fn get_kinetic_friction(&self) -> Float {
+        self.kinetic_friction
+    }
+    pub fn set_friction(&mut self, static_friction : Float, kinetic_friction : Float) {
+        self.static_friction = static_friction;
+        self.kinetic_friction = kinetic_friction;
+    }
+    pub fn get_charge(&self) -> Float {
+        self.charge
+    }
+    pub fn set_charge(&mut self, charge : Float) {
+        self.charge = charge;
+    }
+    pub fn get_linear_drag(&self) -> Float {
+        self.linear_drag
+    }
+    pub fn get_quadratic_drag(&self) -> Float {
+        self.quadratic_drag
+    }
+    pub fn set_drag(&mut self, linear : Float, quadratic : Float) {
+        self.linear_drag = linear;
+        self.quadratic_drag = quadratic;
+    }
+    pub fn get_angle(&self) -> Float {
+        self.angle
+    }
+    pub fn get_angular_velocity(&self) -> Float {
+        self.angular_velocity
+    }
+    fn spin(&mut self, w : Float, t : Float) {
+        //changes the angular velocity from the current angle onwards
+        self.spin_refangle = self.angle;
+        self.spin_reftime = t;
+        self.angular_velocity = w;
+    }
+    pub fn is_resting(&self) -> bool {
+        self.rest_x.is_some() || self.rest_y.is_some()
+    }
+    fn supported_along(&self, x : Float, y : Float) -> bool {
+        //whether pushing the ball in direction (x, y) only presses it into a boundary it rests on
+        self.rest_x.is_some_and(|b| b.pressed_by(x, 0.0) && x != 0.0) || self.rest_y.is_some_and(|b| b.pressed_by(0.0, y) && y != 0.0)
+    }
+    fn path_polynomials(&self, t0 : Float) -> Option<(Polynomial, Polynomial)> {
+        //x and y as polynomials of the time since t0, only possible when the cached functions are polynomials
+        let px = self.cached_x_dyn_function.as_ref()?.to_polynomial()?.shifted(t0 - self.x_reftime);
+        let py = self.cached_y_dyn_function.as_ref()?.to_polynomial()?.shifted(t0 - self.y_reftime);
+        Some((px, py))
+    }
+    pub fn get_vx(&self, t : Float) -> Float {
+        (self.fvx.closure)(t - self.x_reftime).expect("Evaluation Error")
+    }
+    pub fn get_vy(&self, t : Float) -> Float {
+        (self.fvy.closure)(t - self.y_reftime).expect("Evaluation Error")
+    }
+    pub fn get_ax(&self, t : Float) -> Float {
+        //the acceleration the path follows, zero on an axis resting on a boundary
+        (self.fax.closure)(t - self.x_reftime).expect("Evaluation Error")
+    }
+    pub fn get_ay(&self, t : Float) -> Float {
+        (self.fay.closure)(t - self.y_reftime).expect("Evaluation Error")
+    }
+
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Material {
+    Rubber, //bouncy and grippy
+    Steel, //hard, bounces well off other balls and poorly off the ground
+    Wood,
+    Ice, //dead and slippery
+}
+
+impl Material {
+    pub fn bounce(&self) -> (Float, Float) {
+        //ground and free bounce coefficients
+        match self {
+            Material::Rubber => (0.85, 0.85),
+            Material::Steel => (0.6, 0.95),
+            Material::Wood => (0.5, 0.6),
+            Material::Ice => (0.2, 0.3),
+        }
+    }
+    pub fn friction(&self) -> (Float, Float) {
+        //static and kinetic friction coefficients
+        match self {
+            Material::Rubber => (1.0, 0.8),
+            Material::Steel => (0.6, 0.4),
+            Material::Wood => (0.5, 0.3),
+            Material::Ice => (0.05, 0.03),
+        }
     }
-    pub fn get_vy(&self, t : f32) -> f32 {
-        self.cached_y_dyn_function.as_ref()
-            .expect("No cache, unable to get vy!")
-            .differentiated(Var::T)
-            .expect("Differentiation Error")
-            .compile()
-            .expect("Compilation Error")
-            (t - self.y_reftime)
-            .expect("Evaluation Error")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BallError {
+    NotFinite(&'static str), //the named setting is NaN or infinite
+    NonPositiveRadius(Float),
+    NonPositiveMass(Float),
+    NegativeBounce(Float),
+    GainingBounce(Float), //a ground bounce over 1 between two reflecting sides facing each other speeds the ball up on every trip until it overflows
+    NegativeFriction(Float),
+    NegativeDrag(Float),
+    OutsideSpace(Float, Float), //the ball at this position doesn't fit between the space's boundaries
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BallBuilder {
+    x : Float,
+    y : Float,
+    vx : Float,
+    vy : Float,
+    radius : Float,
+    mass : Float,
+    ground_bounce : Float,
+    free_bounce : Float,
+    static_friction : Float,
+    kinetic_friction : Float,
+    color : [f32; 4],
+    shape : InertiaShape,
+    angular_velocity : Float,
+    charge : Float,
+    linear_drag : Float,
+    quadratic_drag : Float,
+}
+
+impl Default for BallBuilder {
+    fn default() -> Self {
+        //a unit ball at rest at the origin
+        BallBuilder {
+            x : 0.0,
+            y : 0.0,
+            vx : 0.0,
+            vy : 0.0,
+            radius : 1.0,
+            mass : 1.0,
+            ground_bounce : 0.8,
+            free_bounce : 0.95,
+            static_friction : DEFAULT_STATIC_FRICTION,
+            kinetic_friction : DEFAULT_KINETIC_FRICTION,
+            color : [1.0, 1.0, 1.0, 1.0],
+            shape : InertiaShape::SolidSphere,
+            angular_velocity : 0.0,
+            charge : 0.0,
+            linear_drag : 0.0,
+            quadratic_drag : 0.0,
+        }
     }
+}
 
+impl BallBuilder {
+    pub fn new() -> BallBuilder {
+        BallBuilder::default()
+    }
+    pub fn position(self, x : Float, y : Float) -> BallBuilder {
+        BallBuilder { x, y, ..self }
+    }
+    pub fn velocity(self, vx : Float, vy : Float) -> BallBuilder {
+        BallBuilder { vx, vy, ..self }
+    }
+    pub fn radius(self, radius : Float) -> BallBuilder {
+        BallBuilder { radius, ..self }
+    }
+    pub fn mass(self, mass : Float) -> BallBuilder {
+        BallBuilder { mass, ..self }
+    }
+    pub fn bounce(self, ground_bounce : Float, free_bounce : Float) -> BallBuilder {
+        BallBuilder { ground_bounce, free_bounce, ..self }
+    }
+    pub fn friction(self, static_friction : Float, kinetic_friction : Float) -> BallBuilder {
+        BallBuilder { static_friction, kinetic_friction, ..self }
+    }
+    pub fn material(self, material : Material) -> BallBuilder {
+        //sets the bounce and friction, later calls to bounce or friction override either
+        let ((ground_bounce, free_bounce), (static_friction, kinetic_friction)) = (material.bounce(), material.friction());
+        BallBuilder { ground_bounce, free_bounce, static_friction, kinetic_friction, ..self }
+    }
+    pub fn color(self, color : [f32; 4]) -> BallBuilder {
+        BallBuilder { color, ..self }
+    }
+    pub fn shape(self, shape : InertiaShape) -> BallBuilder {
+        BallBuilder { shape, ..self }
+    }
+    pub fn spin(self, angular_velocity : Float) -> BallBuilder {
+        BallBuilder { angular_velocity, ..self }
+    }
+    pub fn charge(self, charge : Float) -> BallBuilder {
+        BallBuilder { charge, ..self }
+    }
+    pub fn drag(self, linear_drag : Float, quadratic_drag : Float) -> BallBuilder {
+        BallBuilder { linear_drag, quadratic_drag, ..self }
+    }
+    pub fn check(&self, space : &Space) -> Result<(), BallError> {
+        //everything new_ball_unchecked takes on trust, the first problem found is returned
+        let settings = [
+            ("x", self.x), ("y", self.y), ("vx", self.vx), ("vy", self.vy), ("radius", self.radius), ("mass", self.mass),
+            ("ground bounce", self.ground_bounce), ("free bounce", self.free_bounce), ("static friction", self.static_friction), ("kinetic friction", self.kinetic_friction),
+            ("spin", self.angular_velocity), ("charge", self.charge), ("linear drag", self.linear_drag), ("quadratic drag", self.quadratic_drag),
+        ];
+        if let Some(&(name, _)) = settings.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(BallError::NotFinite(name));
+        }
+        if self.radius <= 0.0 {
+            return Err(BallError::NonPositiveRadius(self.radius));
+        }
+        if self.mass <= 0.0 {
+            return Err(BallError::NonPositiveMass(self.mass));
+        }
+        if let Some(&bounce) = [self.ground_bounce, self.free_bounce].iter().find(|&&bounce| bounce < 0.0) {
+            return Err(BallError::NegativeBounce(bounce));
+        }
+        let reflects = |boundary : Boundary| space.get_boundary_mode(boundary) == BoundaryMode::Reflect;
+        let trapped = (reflects(Boundary::Left) && reflects(Boundary::Right)) || (reflects(Boundary::Floor) && reflects(Boundary::Ceiling));
+        if self.ground_bounce > 1.0 && trapped {
+            return Err(BallError::GainingBounce(self.ground_bounce));
+        }
+        if let Some(&friction) = [self.static_friction, self.kinetic_friction].iter().find(|&&friction| friction < 0.0) {
+            return Err(BallError::NegativeFriction(friction));
+        }
+        if let Some(&drag) = [self.linear_drag, self.quadratic_drag].iter().find(|&&drag| drag < 0.0) {
+            return Err(BallError::NegativeDrag(drag));
+        }
+        //only sides the ball would bounce off or be deleted by, it may start across a wrapping or open one
+        let outside = Boundary::ALL.iter().any(|&boundary| {
+            let (nx, ny) = boundary.normal();
+            matches!(space.get_boundary_mode(boundary), BoundaryMode::Reflect | BoundaryMode::Absorb)
+                && nx * self.x + ny * self.y - space.boundary_offset(boundary) < self.radius
+        });
+        if outside {
+            return Err(BallError::OutsideSpace(self.x, self.y));
+        }
+        Ok(())
+    }
 }
 
 impl Space {
@@ -247,10 +710,36 @@ impl Space {
             time_units : Unit::S.units(),
             space_units : Unit::M.units(),
             mass_units : Unit::KG.units(),
+            fa : AccelerationCache::new(&a),
             a,
-            //pixelx : fn(m : f32) -> usize { (m * 1000.0) as usize }, //space is a meter by a meter
+            //pixelx : fn(m : Float) -> usize { (m * 1000.0) as usize }, //space is a meter by a meter
             elapsed : 0.0,
             balls : Vec::new(),
+            slots : Vec::new(),
+            free_slots : Vec::new(),
+            continuous : true,
+            max_impacts_per_tick : 256,
+            broad_phase : Box::new(broadphase::BruteForce),
+            resting_threshold : 1.0,
+            position_correction : 0.8,
+            penetration_slop : 0.01,
+            position_iterations : 4,
+            velocity_iterations : 8,
+            boundary_modes : [BoundaryMode::Reflect; 4],
+            obstacles : Vec::new(),
+            springs : Vec::new(),
+            constraints : Vec::new(),
+            pivots : Vec::new(),
+            constraint_iterations : 32,
+            ropes : Vec::new(),
+            pair_force : PairForce::Off,
+            pair_force_method : PairForceMethod::Exact,
+            softening : 0.1,
+            integrator : Integrator::Analytic,
+            acceleration_field : None,
+            adaptive_substeps : None,
+            max_substeps : 16,
+            substep_stats : SubstepStats::default(),
         }
     }
 
@@ -266,11 +755,12 @@ impl Space {
         }
     }
 
-    pub fn new_ball_unchecked(&mut self, x : f32, y : f32, vxi : f32, vyi : f32, r : f32, m : f32, b_g : f32, b_f : f32, color : [f32; 4]) {
+    pub fn new_ball_unchecked(&mut self, x : Float, y : Float, vxi : Float, vyi : Float, r : Float, m : Float, b_g : Float, b_f : Float, color : [f32; 4]) -> BallId {
         //Creates a new ball in the space with given parameters including starting location, velocity,
         //and radius, mass,
         //ground bounce coefficient (applied to absolute value of velocity when hitting ground or wall), 
         //and free bounce coefficient (applied to change in velocity when hitting another ball)
+        //nothing is checked, Space::new_ball takes a BallBuilder and validates it first
         let mut ret = Ball::default();
         ret.x = x;
         ret.y = y;
@@ -279,8 +769,111 @@ impl Space {
         ret.color = color;
         ret.ground_bounce = b_g;
         ret.free_bounce = b_f;
-        ret.hard_update_unchecked(&self.a, ret.x, ret.y, vxi, vyi, Recalculate::xy(0.0,0.0));
+        ret.static_friction = DEFAULT_STATIC_FRICTION;
+        ret.kinetic_friction = DEFAULT_KINETIC_FRICTION;
+        ret.hard_update_unchecked(&self.a, ret.x, ret.y, vxi, vyi, Recalculate::xy(self.elapsed, self.elapsed));
+        //the id reuses a freed slot if there is one, its generation already moved past the ball that had it
+        let slot = self.free_slots.pop().unwrap_or_else(|| {
+            self.slots.push(BallSlot { generation : 0, index : None });
+            self.slots.len() - 1
+        });
+        self.slots[slot].index = Some(self.balls.len());
+        let id = BallId { slot, generation : self.slots[slot].generation };
+        ret.id = id;
         self.balls.push(ret);
+        id
+    }
+
+    pub fn new_ball(&mut self, builder : BallBuilder) -> Result<BallId, BallError> {
+        //the checked way to add a ball, see BallBuilder
+        builder.check(self)?;
+        let id = self.new_ball_unchecked(builder.x, builder.y, builder.vx, builder.vy, builder.radius, builder.mass, builder.ground_bounce, builder.free_bounce, builder.color);
+        let ball = self.balls.last_mut().expect("The ball was just added");
+        ball.set_friction(builder.static_friction, builder.kinetic_friction);
+        ball.set_shape(builder.shape);
+        ball.set_charge(builder.charge);
+        ball.set_drag(builder.linear_drag, builder.quadratic_drag);
+        self.spin_ball(id, builder.angular_velocity);
+        Ok(id)
+    }
+
+    fn index_of(&self, id : BallId) -> Option<usize> {
+        //where the ball is in Space::balls right now, none once it has been removed
+        //indices shift down when an earlier ball is removed, ids don't
+        let slot = self.slots.get(id.slot)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.index
+    }
+
+    pub fn get_balls(&self) -> &[Ball] {
+        //every ball in the order they are stored, which shifts when one is removed, hold on to ids rather than positions in here
+        &self.balls
+    }
+
+    pub fn get_ball(&self, id : BallId) -> Option<&Ball> {
+        self.index_of(id).map(|i| &self.balls[i])
+    }
+
+    pub fn get_ball_mut(&mut self, id : BallId) -> Option<&mut Ball> {
+        self.index_of(id).map(|i| &mut self.balls[i])
+    }
+
+    pub fn ball_ids(&self) -> Vec<BallId> {
+        //a snapshot, so balls can be removed while walking through it, get_ball turns removed ones into none
+        self.balls.iter().map(|ball| ball.id).collect()
+    }
+
+    pub fn remove_ball(&mut self, id : BallId) -> Option<Ball> {
+        let i = self.index_of(id)?;
+        Some(self.remove_ball_at(i))
+    }
+
+    pub fn spin_ball(&mut self, id : BallId, w : Float) -> bool {
+        //sets the ball's angular velocity in radians per second, counterclockwise, returns false if it has been removed
+        let elapsed = self.elapsed;
+        match self.get_ball_mut(id) {
+            Some(ball) => ball.spin(w, elapsed),
+            None => return false,
+        }
+        true
+    }
+
+    pub fn set_ball_force(&mut self, id : BallId, force : Option<AccelxyFunction>) -> bool {
+        //gives the ball its own force in newtons, or takes it away, re-integrating its path from where it is now
+        //returns false if the ball has been removed, or the force doesn't sum to newtons over the same time as the space's acceleration
+        let i = match self.index_of(id) {
+            Some(i) => i,
+            None => return false,
+        };
+        if let Some(force) = &force {
+            let per_kg = Unit::KG.units().pow(-1);
+            let fits = force.units().zip(self.a.units()).is_some_and(|((var, var_units, units), accel)| (var, var_units, units * per_kg) == accel);
+            if !fits {
+                return false;
+            }
+        }
+        let ball = &mut self.balls[i];
+        let (vx, vy) = (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed));
+        ball.fforce = force.as_ref().map(AccelerationCache::new);
+        ball.force = force;
+        ball.hard_update(&self.a, ball.x, ball.y, vx, vy, Recalculate::xy(self.elapsed, self.elapsed));
+        true
+    }
+
+    fn set_acceleration(&mut self, a : AccelxyFunction) {
+        //the balls keep their paths until they are next recalculated
+        self.fa = AccelerationCache::new(&a);
+        self.a = a;
+    }
+
+    pub fn get_boundary_mode(&self, boundary : Boundary) -> BoundaryMode {
+        self.boundary_modes[boundary as usize]
+    }
+
+    pub fn set_boundary_mode(&mut self, boundary : Boundary, mode : BoundaryMode) {
+        self.boundary_modes[boundary as usize] = mode;
     }
 
     fn two_mut_vals_in_container<T>(container : &mut Vec<T>, mut i1 : usize, mut i2 : usize) -> (&mut T, &mut T) {
@@ -299,11 +892,21 @@ impl Space {
         }
         (reta,retb)
     }
-    fn collision_vs(m1 : f32, m2 : f32, v1 : f32, v2 : f32) -> (f32,f32) {
+    fn coulomb(jt_stick : Float, jn : Float, static_friction : Float, kinetic_friction : Float) -> Float {
+        //limits the tangential impulse that would stop the slip to what friction can supply for normal impulse jn
+        //within the static limit the surfaces stick, past it they skid with kinetic friction against the slip
+        if jt_stick.abs() <= static_friction * jn {
+            jt_stick
+        } else {
+            kinetic_friction * jn * jt_stick.signum()
+        }
+    }
+    fn collision_vs(m1 : Float, m2 : Float, v1 : Float, v2 : Float) -> (Float,Float) {
         //calculates exit velocities of two objects colliding in one dimension
         (2.0*m2*v2/(m1+m2) + (m1-m2)/(m1+m2)*v1, 2.0*m1*v1/(m1+m2) - (m1-m2)/(m1+m2)*v2)
     }
-    pub fn exert_collision(&mut self, i : usize, j : usize) {
+    fn exert_collision(&mut self, i : usize, j : usize) {
+        //i and j are positions in Space::balls, only good for the current step, so this stays with the stepping code
         //take two round balls and bounce them from each other, assuming they are touching
         //two balls far apart will collide as if one of them is big enough to be touching the other; their size isn't known in this function
         //if the balls aren't headed towards each other, they won't collide
@@ -324,15 +927,27 @@ impl Space {
         let (b1vL , b2vL ) = (b1v * b1v_θ_ll.sin(), b2v * b2v_θ_ll.sin()); //velocities off collision axis, L represents perpendicular
 
         //abort collision if the balls aren't headed towards each other
-        let avll = b1vll;
-        let bvll = b2vll;
-        if !(bvll.abs() > avll.abs() && (bvll < 0.0 || avll > 0.0) || (bvll < 0.0 && avll > 0.0)) {
+        //the axis points from b1 to b2, so they approach when b1 is faster along it than b2
+        if b1vll - b2vll <= 0.0 {
             return; //no collision, they aren't exerting force;
         }
 
         //collide balls
-        let (b1vll_f , b2vll_f ) = Self::collision_vs(b1.mass, b2.mass, b1vll , b2vll); //f means final
-        let (b1vll_fb, b2vll_fb) = (b1vll_f * b1.free_bounce, b2vll_f * b2.free_bounce); //apply bounce coefficients, b means bounce
+        let resting = b1vll - b2vll < self.resting_threshold;
+        let (b1vll_fb, b2vll_fb) = if resting {
+            //too slow to bounce, settle into contact by giving both the same velocity along the axis
+            //a ball resting on a boundary behind the contact can't be pushed, so the other matches it instead
+            let (cos, sin) = (collision_θ.cos(), collision_θ.sin());
+            let v = match (b1.supported_along(-cos, -sin), b2.supported_along(cos, sin)) {
+                (true, false) => b1vll,
+                (false, true) => b2vll,
+                _ => (b1.mass * b1vll + b2.mass * b2vll) / (b1.mass + b2.mass),
+            };
+            (v, v)
+        } else {
+            let (b1vll_f , b2vll_f ) = Self::collision_vs(b1.mass, b2.mass, b1vll , b2vll); //f means final
+            (b1vll_f * b1.free_bounce, b2vll_f * b2.free_bounce) //apply bounce coefficients, b means bounce
+        };
         
         //calculate new total velocities and their θs
         let (b1v_fb_θ_ll, b2v_fb_θ_ll) = (b1vL.atan2(b1vll_fb), b2vL.atan2(b2vll_fb));
@@ -342,73 +957,1906 @@ impl Space {
 
 
         //calculate x and y components and put back into ball
-        let (b1vx_fb, b1vy_fb, b2vx_fb, b2vy_fb) = (b1v_fb * b1v_fb_θ.cos(), b1v_fb * b1v_fb_θ.sin(), b2v_fb * b2v_fb_θ.cos(), b2v_fb * b2v_fb_θ.sin());
-        b1.hard_update(&self.a, b1.x, b1.y, b1vx_fb, b1vy_fb, Recalculate::xy(self.elapsed, self.elapsed));
-        b2.hard_update(&self.a, b2.x, b2.y, b2vx_fb, b2vy_fb, Recalculate::xy(self.elapsed, self.elapsed));
-    }
-
-    pub fn search_collision_pairs(&self) -> Vec<(usize, usize)> {
-        //O(n^2)) function searching for colliding balls with pythagorean theorem and two for loops
-        let mut ret = Vec::<(usize, usize)>::new();
-        for i in 0..(self.balls.len()-1) {
-            for j in (i+1)..self.balls.len() {
-                if (self.balls[i].x - self.balls[j].x).hypot(self.balls[i].y - self.balls[j].y) <= self.balls[i].radius + self.balls[j].radius {
-                    ret.push((i,j));
+        let (mut b1vx_fb, mut b1vy_fb, mut b2vx_fb, mut b2vy_fb) = (b1v_fb * b1v_fb_θ.cos(), b1v_fb * b1v_fb_θ.sin(), b2v_fb * b2v_fb_θ.cos(), b2v_fb * b2v_fb_θ.sin());
+
+        //exchange a tangential friction impulse at the contact point, coupling spin and motion
+        //spin of ω about a center moves the contact point ω * r along the tangent, the same way for b1 and the opposite for b2
+        //settled contacts are left alone, repeated small impulses between resting balls only make them jitter
+        if !resting {
+            let (tx, ty) = (-collision_θ.sin(), collision_θ.cos());
+            let (b1i, b2i) = (b1.get_moment_of_inertia(), b2.get_moment_of_inertia());
+            let slip = (b1vx_fb * tx + b1vy_fb * ty + b1.angular_velocity * b1.radius) - (b2vx_fb * tx + b2vy_fb * ty - b2.angular_velocity * b2.radius);
+            let jt_stick = slip / (1.0 / b1.mass + b1.radius * b1.radius / b1i + 1.0 / b2.mass + b2.radius * b2.radius / b2i);
+            let jn = b1.mass * (b1vll_fb - b1vll).abs();
+            let jt = Self::coulomb(jt_stick, jn, (b1.static_friction * b2.static_friction).sqrt(), (b1.kinetic_friction * b2.kinetic_friction).sqrt());
+            (b1vx_fb, b1vy_fb) = (b1vx_fb - jt * tx / b1.mass, b1vy_fb - jt * ty / b1.mass);
+            (b2vx_fb, b2vy_fb) = (b2vx_fb + jt * tx / b2.mass, b2vy_fb + jt * ty / b2.mass);
+            let (b1w, b2w) = (b1.angular_velocity - jt * b1.radius / b1i, b2.angular_velocity - jt * b2.radius / b2i);
+            b1.spin(b1w, self.elapsed);
+            b2.spin(b2w, self.elapsed);
+        }
+
+        self.redirect(i, b1vx_fb, b1vy_fb);
+        self.redirect(j, b2vx_fb, b2vy_fb);
+    }
+
+    fn closing_speed(&self, i : usize, j : usize) -> Float {
+        //how fast balls i and j approach along the line between their centers, negative when separating
+        let (b1, b2) = (&self.balls[i], &self.balls[j]);
+        let d = (b2.x - b1.x).hypot(b2.y - b1.y);
+        if d == 0.0 {
+            return 0.0;
+        }
+        let (dvx, dvy) = (b1.get_vx(self.elapsed) - b2.get_vx(self.elapsed), b1.get_vy(self.elapsed) - b2.get_vy(self.elapsed));
+        (dvx * (b2.x - b1.x) + dvy * (b2.y - b1.y)) / d
+    }
+
+    fn solve_contacts(&mut self, contacts : &[(usize, usize)]) {
+        //sequential impulses over every contact at once, each pass bounces whichever pairs are still closing
+        //a single pass in index order would leave the result depending on that order, repeating lets an impulse travel along a chain
+        for _ in 0..self.velocity_iterations.max(1) {
+            let mut closing = false;
+            for &(i, j) in contacts {
+                if self.closing_speed(i, j) > 0.0 {
+                    if DBG_INFO_TWO_COLLISIONS {println!("ball {} had collisions with ball {}", i, j);}
+                    self.exert_collision(i, j);
+                    closing = true;
+                }
+            }
+            if !closing {
+                break;
+            }
+        }
+    }
+
+    fn contact_neighbours(&mut self, h : Float) -> Vec<Vec<BallId>> {
+        //balls whose swept boxes over the next h overlap, listed both ways and indexed by BallId::slot
+        //worked out once a tick so impacts only look through these for the contacts they spread through, by id so balls absorbed along the way just stop resolving
+        let paths : Vec<Option<(Polynomial, Polynomial)>> = self.balls.iter().map(|ball| ball.path_polynomials(self.elapsed)).collect();
+        let sweeps = self.swept_bounds(&paths, h, 1.0 + CONTACT_TOLERANCE);
+        let mut neighbours = vec![Vec::new(); self.slots.len()];
+        for (i, j) in self.broad_phase.pairs(&sweeps) {
+            let (a, b) = (self.balls[i].id, self.balls[j].id);
+            neighbours[a.slot].push(b);
+            neighbours[b.slot].push(a);
+        }
+        neighbours
+    }
+
+    fn contact_group(&self, i : usize, j : usize, neighbours : &[Vec<BallId>]) -> Vec<(usize, usize)> {
+        //every touching pair linked to the pair (i, j) through other touching balls, starting with (i, j) itself
+        //walks out from i and j breadth first, each ball's neighbours are checked once when it is reached
+        //a ball redirected or wrapped out of its sweep earlier in the tick can miss a contact here, the discrete pass at the end of the tick still has it
+        let touching = |a : usize, b : usize| {
+            let (p, q) = (&self.balls[a], &self.balls[b]);
+            (p.x - q.x).hypot(p.y - q.y) <= (p.radius + q.radius) * (1.0 + CONTACT_TOLERANCE)
+        };
+        let mut group = vec![(i, j)];
+        let mut members = vec![i, j];
+        let (mut reached, mut walked) = (vec![false; self.balls.len()], vec![false; self.balls.len()]);
+        (reached[i], reached[j]) = (true, true);
+        let mut k = 0;
+        while k < members.len() {
+            let member = members[k];
+            walked[member] = true;
+            for &id in &neighbours[self.balls[member].id.slot] {
+                let other = match self.index_of(id) {
+                    Some(other) => other,
+                    None => continue,
+                };
+                //a pair with a ball already walked was taken from that ball's side
+                if walked[other] || (member, other) == (i, j) || (member, other) == (j, i) || !touching(member, other) {
+                    continue;
+                }
+                group.push((member, other));
+                if !reached[other] {
+                    reached[other] = true;
+                    members.push(other);
                 }
             }
+            k += 1;
+        }
+        group
+    }
+
+    fn grip(&mut self, i : usize, (nx, ny) : (Float, Float), vx : Float, vy : Float, jn : Float) -> (Float, Float) {
+        //tangential friction impulse from a static surface with normal (nx, ny) against the ball's contact point slipping along it
+        //returns the new velocity
+        let (tx, ty) = (-ny, nx);
+        let ball = &mut self.balls[i];
+        let inertia = ball.get_moment_of_inertia();
+        let slip = vx * tx + vy * ty - ball.angular_velocity * ball.radius; //the contact point sits at -r * normal
+        let jt_stick = -slip / (1.0 / ball.mass + ball.radius * ball.radius / inertia);
+        let jt = Self::coulomb(jt_stick, jn, ball.static_friction, ball.kinetic_friction);
+        let w = ball.angular_velocity - jt * ball.radius / inertia;
+        ball.spin(w, self.elapsed);
+        (vx + jt * tx / ball.mass, vy + jt * ty / ball.mass)
+    }
+
+    fn redirect(&mut self, i : usize, vx : Float, vy : Float) {
+        //gives ball i a new velocity from where it is now
+        //an axis resting on a boundary stays held if the new velocity only pushes it further into the boundary
+        let ball = &mut self.balls[i];
+        let hold_x = ball.rest_x.is_some_and(|b| b.pressed_by(vx, 0.0));
+        let hold_y = ball.rest_y.is_some_and(|b| b.pressed_by(0.0, vy));
+        let t = Recalculate {
+            val : (
+                if hold_x { MaybeNew::NoUpdate } else { MaybeNew::Update(self.elapsed) },
+                if hold_y { MaybeNew::NoUpdate } else { MaybeNew::Update(self.elapsed) },
+            ),
+        };
+        ball.hard_update(&self.a, ball.x, ball.y, vx, vy, t);
+    }
+
+    fn settle(&mut self, i : usize, boundary : Boundary) {
+        //pins the ball against the boundary, the normal force cancels the acceleration on that axis until it pulls away
+        let ball = &mut self.balls[i];
+        let held = match boundary {
+            Boundary::Left => self.x1 + ball.radius,
+            Boundary::Right => self.x2 - ball.radius,
+            Boundary::Floor => self.floor + ball.radius,
+            Boundary::Ceiling => self.y2 - ball.radius,
+        };
+        let path : Box<dyn Function> = Box::new(Polynomial::from_coefficients(Var::T, self.time_units, self.space_units, &[held]));
+        match boundary {
+            Boundary::Left | Boundary::Right => {
+                ball.x = held;
+                ball.x_reftime = self.elapsed;
+                ball.cached_x_dyn_function = Some(path);
+                ball.rest_x = Some(boundary);
+            }
+            Boundary::Floor | Boundary::Ceiling => {
+                ball.y = held;
+                ball.y_reftime = self.elapsed;
+                ball.cached_y_dyn_function = Some(path);
+                ball.rest_y = Some(boundary);
+            }
         }
-        ret
+        ball.soft_update_unchecked();
     }
 
-    pub fn tick(&mut self, dt: f32) {
-        self.elapsed += dt;
-        let mut i = 0;
+    fn release_resting(&mut self) {
+        //lets go of contacts the acceleration has turned away from
         for ball in &mut self.balls {
-            //Keep track of the cached calculus functions
-            //Check if last acceleration for ball was different and then recompile the cached calculus polynomial if so
-            let (x, y) = ((ball.fx.closure)(self.elapsed - ball.x_reftime), (ball.fy.closure)(self.elapsed - ball.y_reftime));
-            ball.x = x.unwrap();
-            ball.y = y.unwrap();
-            if ball.x - ball.radius < self.x1 {
-                let vx = ball.get_vx(self.elapsed); let vy = ball.get_vy(self.elapsed); let b = ball.get_ground_bounce();
-                if vx < 0.0 {
-                    if DBG_INFO_ONE_COLLISIONS { println!("ball {} had left x collision with x velocity {}, which will be reduced to {}", i, vx, -vx * b); }
-                    ball.hard_update_unchecked(&self.a, ball.get_x(), ball.get_y(), -vx * b, vy, Recalculate::x(self.elapsed));
+            if let Some(boundary) = ball.rest_x {
+                let (ax, _) = ball.acceleration_at(&self.fa, self.elapsed - ball.x_reftime);
+                if !boundary.pressed_by(ax, 0.0) {
+                    ball.hard_update_unchecked(&self.a, ball.x, ball.y, 0.0, 0.0, Recalculate::x(self.elapsed));
                 }
             }
-            if ball.x + ball.radius > self.x2 {
-                let vx = ball.get_vx(self.elapsed); let vy = ball.get_vy(self.elapsed); let b = ball.get_ground_bounce();
-                if vx > 0.0 {
-                    if DBG_INFO_ONE_COLLISIONS { println!("ball {} had right x collision with x velocity {}, which will be reduced to {}", i, vx, -vx * b); }
-                    ball.hard_update_unchecked(&self.a, ball.get_x(), ball.get_y(), -vx * b, vy, Recalculate::x(self.elapsed));
+            if let Some(boundary) = ball.rest_y {
+                let (_, ay) = ball.acceleration_at(&self.fa, self.elapsed - ball.y_reftime);
+                if !boundary.pressed_by(0.0, ay) {
+                    ball.hard_update_unchecked(&self.a, ball.x, ball.y, 0.0, 0.0, Recalculate::y(self.elapsed));
                 }
             }
-            if ball.y - ball.radius < self.floor {
-                let vx = ball.get_vx(self.elapsed); let vy = ball.get_vy(self.elapsed); let b = ball.get_ground_bounce();
-                if vy < 0.0 {
-                    if DBG_INFO_ONE_COLLISIONS { println!("ball {} had y collision with y velocity {}, which will be reduced to {}", i, vy, -vy * b); }
-                    ball.hard_update_unchecked(&self.a, ball.get_x(), ball.get_y(), vx, -vy * b, Recalculate::y(self.elapsed));
+        }
+    }
+
+    pub fn search_collision_pairs(&mut self) -> Vec<(usize, usize)> {
+        //broad phase finds balls with overlapping bounding boxes, then the pythagorean theorem keeps the ones actually touching
+        let bounds : Vec<Aabb> = self.balls.iter().map(|ball| Aabb::around(ball.x, ball.y, ball.radius)).collect();
+        let balls = &self.balls;
+        self.broad_phase.pairs(&bounds).into_iter().filter(|&(i, j)| {
+            (balls[i].x - balls[j].x).hypot(balls[i].y - balls[j].y) <= balls[i].radius + balls[j].radius
+        }).collect()
+    }
+
+    fn boundary_offset(&self, boundary : Boundary) -> Float {
+        //the boundary is the line where normal . (x, y) equals this
+        match boundary {
+            Boundary::Left => self.x1,
+            Boundary::Right => -self.x2,
+            Boundary::Floor => self.floor,
+            Boundary::Ceiling => -self.y2,
+        }
+    }
+
+    fn boundary_reach(&self, i : usize, boundary : Boundary) -> Option<Float> {
+        //how far from the boundary ball i's center is when the boundary acts on it, None if it never does
+        match self.get_boundary_mode(boundary) {
+            BoundaryMode::Reflect | BoundaryMode::Absorb => Some(self.balls[i].radius),
+            BoundaryMode::Wrap => Some(0.0),
+            BoundaryMode::Open => None,
+        }
+    }
+
+    fn touches_boundary(&self, i : usize, boundary : Boundary) -> bool {
+        let ball = &self.balls[i];
+        let (nx, ny) = boundary.normal();
+        self.boundary_reach(i, boundary).is_some_and(|reach| nx * ball.x + ny * ball.y - self.boundary_offset(boundary) < reach)
+    }
+
+    fn hit_boundary(&mut self, i : usize, boundary : Boundary) -> bool {
+        //applies the boundary's mode to ball i, returns false if the ball was deleted
+        match self.get_boundary_mode(boundary) {
+            BoundaryMode::Reflect => self.bounce_boundary(i, boundary),
+            BoundaryMode::Absorb => {
+                if DBG_INFO_ONE_COLLISIONS { println!("ball {} was absorbed by the {:?} boundary", i, boundary); }
+                self.remove_ball_at(i);
+                return false;
+            }
+            BoundaryMode::Wrap => self.wrap_boundary(i, boundary),
+            BoundaryMode::Open => (),
+        }
+        true
+    }
+
+    fn remove_ball_at(&mut self, i : usize) -> Ball {
+        //links to the ball go with it, balls after it move down an index and their slots follow
+        let ball = self.balls.remove(i);
+        let id = ball.id;
+        let slot = &mut self.slots[id.slot];
+        (slot.generation, slot.index) = (slot.generation.wrapping_add(1), None);
+        self.free_slots.push(id.slot);
+        for (k, later) in self.balls.iter().enumerate().skip(i) {
+            self.slots[later.id.slot].index = Some(k);
+        }
+        self.springs.retain(|spring| !spring.joins(id));
+        self.constraints.retain(|constraint| !constraint.joins(id));
+        self.pivots.retain(|pivot| !pivot.joins(id));
+        self.ropes = self.ropes.iter().map(|rope| rope.after_removal(id)).collect();
+        ball
+    }
+
+    fn wrap_boundary(&mut self, i : usize, boundary : Boundary) {
+        //carries a ball leaving through the boundary across to the opposite side, the period is the width or height of the space
+        //one already heading back in was just wrapped here from the other side, so it is left alone
+        let ball = &self.balls[i];
+        if !boundary.pressed_by(ball.get_vx(self.elapsed), ball.get_vy(self.elapsed)) {
+            return;
+        }
+        let (nx, ny) = boundary.normal();
+        let (width, height) = (self.x2 - self.x1, self.y2 - self.floor);
+        let (x, y) = (ball.x + nx * width, ball.y + ny * height);
+        self.relocate(i, x, y);
+    }
+
+    fn bounce_boundary(&mut self, i : usize, boundary : Boundary) {
+        //reflects the velocity component into the boundary, scaled by the ground bounce coefficient
+        //a ball already heading away from the boundary is left alone, and one arriving too slowly settles against it
+        let ball = &self.balls[i];
+        let vx = ball.get_vx(self.elapsed); let vy = ball.get_vy(self.elapsed); let b = ball.get_ground_bounce();
+        let (normal_v, reftime) = match boundary {
+            Boundary::Left | Boundary::Right => (vx, ball.x_reftime),
+            Boundary::Floor | Boundary::Ceiling => (vy, ball.y_reftime),
+        };
+        let (ax, ay) = ball.acceleration_at(&self.fa, self.elapsed - reftime);
+        if normal_v.abs() < self.resting_threshold && boundary.pressed_by(vx, vy) && boundary.pressed_by(ax, ay) {
+            self.settle(i, boundary);
+            return;
+        }
+        let (vx_f, vy_f) = match boundary {
+            Boundary::Left if vx < 0.0 => {
+                if DBG_INFO_ONE_COLLISIONS { println!("ball {} had left x collision with x velocity {}, which will be reduced to {}", i, vx, -vx * b); }
+                (-vx * b, vy)
+            }
+            Boundary::Right if vx > 0.0 => {
+                if DBG_INFO_ONE_COLLISIONS { println!("ball {} had right x collision with x velocity {}, which will be reduced to {}", i, vx, -vx * b); }
+                (-vx * b, vy)
+            }
+            Boundary::Floor if vy < 0.0 => {
+                if DBG_INFO_ONE_COLLISIONS { println!("ball {} had y collision with y velocity {}, which will be reduced to {}", i, vy, -vy * b); }
+                (vx, -vy * b)
+            }
+            Boundary::Ceiling if vy > 0.0 => {
+                if DBG_INFO_ONE_COLLISIONS { println!("ball {} had ceiling collision with y velocity {}, which will be reduced to {}", i, vy, -vy * b); }
+                (vx, -vy * b)
+            }
+            _ => return,
+        };
+        let jn = self.balls[i].mass * ((vx_f - vx).abs() + (vy_f - vy).abs()); //only the normal component changed
+        let (vx, vy) = self.grip(i, boundary.normal(), vx_f, vy_f, jn);
+        self.redirect(i, vx, vy);
+    }
+
+    fn obstacle_edges(&self) -> Vec<Segment> {
+        self.obstacles.iter().flat_map(|obstacle| obstacle.edges()).collect()
+    }
+
+    fn touches_edge(&self, i : usize, edge : &Segment) -> bool {
+        let ball = &self.balls[i];
+        let (cx, cy) = edge.closest_point(ball.x, ball.y);
+        (ball.x - cx).hypot(ball.y - cy) < ball.radius
+    }
+
+    fn bounce_edge(&mut self, i : usize, edge : &Segment) {
+        //reflects the velocity about the normal at the point of the edge closest to the ball, scaled by the ground bounce coefficient
+        //edges can slope, so a contact arriving too slowly can't settle on an axis like the boundaries do, it just stops bouncing
+        let ball = &self.balls[i];
+        let (cx, cy) = edge.closest_point(ball.x, ball.y);
+        let d = (ball.x - cx).hypot(ball.y - cy);
+        if d == 0.0 {
+            return;
+        }
+        let (nx, ny) = ((ball.x - cx) / d, (ball.y - cy) / d);
+        let (vx, vy) = (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed));
+        let normal_v = vx * nx + vy * ny;
+        if normal_v >= 0.0 {
+            return;
+        }
+        let b = if -normal_v < self.resting_threshold { 0.0 } else { ball.get_ground_bounce() };
+        if DBG_INFO_ONE_COLLISIONS { println!("ball {} hit an obstacle with normal velocity {}, which will be reduced to {}", i, normal_v, -normal_v * b); }
+        let dv = -(1.0 + b) * normal_v;
+        let (mut dvx, mut dvy) = (dv * nx, dv * ny);
+        //a push into a boundary the ball rests on is taken up by that boundary, so the free axis has to undo the whole approach
+        let (x_supported, y_supported) = (ball.supported_along(dvx, 0.0), ball.supported_along(0.0, dvy));
+        if (x_supported && (y_supported || ny == 0.0)) || (y_supported && nx == 0.0) {
+            return;
+        } else if x_supported {
+            (dvx, dvy) = (0.0, dv / ny);
+        } else if y_supported {
+            (dvx, dvy) = (dv / nx, 0.0);
+        }
+        let (mut vx, mut vy) = (vx + dvx, vy + dvy);
+        //wedged against a boundary the ball can only slide along its free axis, so there is no slip for friction to act on
+        if !x_supported && !y_supported {
+            let jn = ball.mass * dv;
+            (vx, vy) = self.grip(i, (nx, ny), vx, vy, jn);
+        }
+        self.redirect(i, vx, vy);
+    }
+
+    fn resting_friction(&mut self, dt : Float) {
+        //a ball resting on a boundary is pressed into it by the normal force cancelling its acceleration
+        //over the tick that force supplies a friction impulse against any slip, so sliding balls skid into rolling
+        for i in 0..self.balls.len() {
+            let ball = &self.balls[i];
+            let mut velocity = (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed));
+            let mut changed = false;
+            for (rest, reftime) in [(ball.rest_x, ball.x_reftime), (ball.rest_y, ball.y_reftime)] {
+                let boundary = match rest {
+                    Some(boundary) => boundary,
+                    None => continue,
+                };
+                let (nx, ny) = boundary.normal();
+                let ball = &self.balls[i];
+                let slip = velocity.0 * -ny + velocity.1 * nx - ball.angular_velocity * ball.radius;
+                if slip.abs() < CLOSING_SPEED_EPSILON {
+                    continue;
                 }
+                let (ax, ay) = ball.acceleration_at(&self.fa, self.elapsed - reftime);
+                let jn = ball.mass * (ax * nx + ay * ny).abs() * dt;
+                velocity = self.grip(i, (nx, ny), velocity.0, velocity.1, jn);
+                changed = true;
             }
-            ball.soft_update_unchecked();
-            i += 1;
+            if changed {
+                self.redirect(i, velocity.0, velocity.1);
+            }
+        }
+    }
+
+    fn apply_drag(&mut self, dt : Float) {
+        //drag depends on velocity so it can't go into the polynomial paths, instead each tick it is solved on its own
+        //with quadratic drag linearized at the current speed, v relaxes exponentially towards acceleration / k over the tick
+        //the velocity change is applied up front so the path ends the tick at exactly that velocity, and terminal velocity is exact
+        for i in 0..self.balls.len() {
+            let ball = &self.balls[i];
+            let (vx, vy) = (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed));
+            let k = (ball.linear_drag + ball.quadratic_drag * vx.hypot(vy)) / ball.mass;
+            if k <= 0.0 {
+                continue;
+            }
+            //a resting axis has its acceleration cancelled by the boundary
+            let ax = if ball.rest_x.is_some() { 0.0 } else { ball.acceleration_at(&self.fa, self.elapsed - ball.x_reftime).0 };
+            let ay = if ball.rest_y.is_some() { 0.0 } else { ball.acceleration_at(&self.fa, self.elapsed - ball.y_reftime).1 };
+            let (decay, gain) = ((-k * dt).exp(), -(-k * dt).exp_m1() / k); //gain is the integral of the decay over the tick
+            let start = |v : Float, a : Float| v * decay + a * (gain - dt);
+            self.redirect(i, start(vx, ax), start(vy, ay));
         }
-        for pair in self.search_collision_pairs() {
-            if DBG_INFO_TWO_COLLISIONS {println!("ball {} had collisions with ball {}", pair.0, pair.1);}
-            self.exert_collision(pair.0, pair.1);
+    }
+
+    fn apply_pair_forces(&mut self, dt : Float) {
+        //forces between balls depend on where they all are, so like drag they are applied as a kick at the start of each tick
+        //kicking velocities before the paths move the balls is semi-implicit euler, which keeps orbits from spiraling out
+        let bodies : Vec<nbody::Body> = match self.pair_force {
+            PairForce::Off => return,
+            PairForce::Gravity(_) => self.balls.iter().map(|ball| nbody::Body { x : ball.x, y : ball.y, source : ball.mass }).collect(),
+            PairForce::Coulomb(_) => self.balls.iter().map(|ball| nbody::Body { x : ball.x, y : ball.y, source : ball.charge }).collect(),
+        };
+        let field = match self.pair_force_method {
+            PairForceMethod::Exact => nbody::field_exact(&bodies, self.softening),
+            PairForceMethod::Cutoff(range) => {
+                let bounds : Vec<Aabb> = bodies.iter().map(|body| Aabb::around(body.x, body.y, range / 2.0)).collect();
+                let pairs : Vec<(usize, usize)> = self.broad_phase.pairs(&bounds).into_iter().filter(|&(i, j)| {
+                    (bodies[i].x - bodies[j].x).hypot(bodies[i].y - bodies[j].y) <= range
+                }).collect();
+                nbody::field_pairs(&bodies, &pairs, self.softening)
+            }
+            PairForceMethod::BarnesHut(theta) => nbody::field_barnes_hut(&bodies, theta, self.softening),
+        };
+        for (i, (ex, ey)) in field.into_iter().enumerate() {
+            let ball = &self.balls[i];
+            let scale = match self.pair_force {
+                PairForce::Gravity(g) => -g,
+                PairForce::Coulomb(k) => k * ball.charge / ball.mass,
+                PairForce::Off => 0.0,
+            };
+            if scale * ex == 0.0 && scale * ey == 0.0 {
+                continue;
+            }
+            let (vx, vy) = (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed));
+            self.redirect(i, vx + scale * ex * dt, vy + scale * ey * dt);
         }
     }
 
-    pub fn get_elapsed(&self) -> f32 {
-        self.elapsed
+    fn link_axis(&self, a : usize, b : usize) -> Option<(Float, Float, Float)> {
+        //unit vector from ball a to ball b and the distance between them, none if they sit on top of each other
+        let (dx, dy) = (self.balls[b].x - self.balls[a].x, self.balls[b].y - self.balls[a].y);
+        let d = dx.hypot(dy);
+        if d == 0.0 {
+            return None;
+        }
+        Some((dx / d, dy / d, d))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::kinematics::Monomial;
-    use crate::kinematics::Polynomial;
-    use super::*;
+    fn link_ends(&self, a : BallId, b : BallId) -> Option<(usize, usize)> {
+        //where a link's two balls are in Space::balls right now
+        Some((self.index_of(a)?, self.index_of(b)?))
+    }
+
+    fn apply_springs(&mut self, dt : Float) {
+        //like the pair forces, springs kick both ends at the start of each tick, equal and opposite along the line between them
+        for k in 0..self.springs.len() {
+            let spring = self.springs[k];
+            let (i, j) = match self.link_ends(spring.a, spring.b) {
+                Some(ends) => ends,
+                None => continue,
+            };
+            let (nx, ny, d) = match self.link_axis(i, j) {
+                Some(axis) => axis,
+                None => continue,
+            };
+            let (a, b) = (&self.balls[i], &self.balls[j]);
+            let (avx, avy, bvx, bvy) = (a.get_vx(self.elapsed), a.get_vy(self.elapsed), b.get_vx(self.elapsed), b.get_vy(self.elapsed));
+            let impulse = spring.tension(d, (bvx - avx) * nx + (bvy - avy) * ny) * dt;
+            let (ma, mb) = (a.mass, b.mass);
+            self.redirect(i, avx + nx * impulse / ma, avy + ny * impulse / ma);
+            self.redirect(j, bvx - nx * impulse / mb, bvy - ny * impulse / mb);
+        }
+    }
+
+    fn enforce_constraints(&mut self) {
+        //rods and pivots are solved after the collisions in the tick, first moving the ends back to length, then removing any velocity along the link
+        //rods are split by inverse mass, a pivot's point doesn't move, and both are repeated so chains of links settle together
+        if self.constraints.is_empty() && self.pivots.is_empty() {
+            return;
+        }
+        //the ids are looked up once, nothing is removed while the links are solved
+        let rods : Vec<(usize, usize, DistanceConstraint)> = self.constraints.iter().filter_map(|&constraint| {
+            let (a, b) = self.link_ends(constraint.a, constraint.b)?;
+            Some((a, b, constraint))
+        }).collect();
+        let pins : Vec<(usize, Pivot)> = self.pivots.iter().filter_map(|&pivot| Some((self.index_of(pivot.ball)?, pivot))).collect();
+        let mut joined = vec![false; self.balls.len()];
+        for &(a, b, _) in &rods {
+            (joined[a], joined[b]) = (true, true);
+        }
+        for &(i, _) in &pins {
+            joined[i] = true;
+        }
+        let inverse_masses : Vec<Float> = self.balls.iter().map(|ball| 1.0 / ball.mass).collect();
+        let mut positions : Vec<(Float, Float)> = self.balls.iter().map(|ball| (ball.x, ball.y)).collect();
+        for _ in 0..self.constraint_iterations.max(1) {
+            for &(a, b, constraint) in &rods {
+                let ((x1, y1), (x2, y2)) = (positions[a], positions[b]);
+                let d = (x2 - x1).hypot(y2 - y1);
+                if d == 0.0 || (constraint.slack && d <= constraint.length) {
+                    continue;
+                }
+                let (nx, ny) = ((x2 - x1) / d, (y2 - y1) / d);
+                let push = (d - constraint.length) / (inverse_masses[a] + inverse_masses[b]);
+                positions[a] = (x1 + nx * push * inverse_masses[a], y1 + ny * push * inverse_masses[a]);
+                positions[b] = (x2 - nx * push * inverse_masses[b], y2 - ny * push * inverse_masses[b]);
+            }
+            for &(i, pivot) in &pins {
+                let (x, y) = positions[i];
+                let d = (x - pivot.x).hypot(y - pivot.y);
+                if d == 0.0 || (pivot.slack && d <= pivot.length) {
+                    continue;
+                }
+                let scale = pivot.length / d;
+                positions[i] = (pivot.x + (x - pivot.x) * scale, pivot.y + (y - pivot.y) * scale);
+            }
+        }
+        for (i, &(x, y)) in positions.iter().enumerate() {
+            if joined[i] {
+                self.relocate(i, x, y);
+            }
+        }
+        let mut velocities : Vec<(Float, Float)> = self.balls.iter().map(|ball| (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed))).collect();
+        for _ in 0..self.constraint_iterations.max(1) {
+            for &(a, b, constraint) in &rods {
+                let (nx, ny, d) = match self.link_axis(a, b) {
+                    Some(axis) => axis,
+                    None => continue,
+                };
+                let ((v1x, v1y), (v2x, v2y)) = (velocities[a], velocities[b]);
+                let impulse = ((v2x - v1x) * nx + (v2y - v1y) * ny) / (inverse_masses[a] + inverse_masses[b]);
+                //slack links only stop the ends pulling apart once taut
+                if constraint.slack && (d < constraint.length * (1.0 - TAUT_TOLERANCE) || impulse <= 0.0) {
+                    continue;
+                }
+                velocities[a] = (v1x + nx * impulse * inverse_masses[a], v1y + ny * impulse * inverse_masses[a]);
+                velocities[b] = (v2x - nx * impulse * inverse_masses[b], v2y - ny * impulse * inverse_masses[b]);
+            }
+            for &(i, pivot) in &pins {
+                let ball = &self.balls[i];
+                let (dx, dy) = (ball.x - pivot.x, ball.y - pivot.y);
+                let d = dx.hypot(dy);
+                if d == 0.0 {
+                    continue;
+                }
+                let (vx, vy) = velocities[i];
+                let along = (vx * dx + vy * dy) / d;
+                if pivot.slack && (d < pivot.length * (1.0 - TAUT_TOLERANCE) || along <= 0.0) {
+                    continue;
+                }
+                velocities[i] = (vx - dx / d * along, vy - dy / d * along);
+            }
+        }
+        for (i, (vx, vy)) in velocities.into_iter().enumerate() {
+            if joined[i] {
+                self.redirect(i, vx, vy);
+            }
+        }
+    }
+
+    pub fn pin(&mut self, a : BallId, b : BallId) -> bool {
+        //joins two balls with a rod as long as they are apart now, returns false if either has been removed
+        let (i, j) = match self.link_ends(a, b) {
+            Some(ends) => ends,
+            None => return false,
+        };
+        let length = (self.balls[j].x - self.balls[i].x).hypot(self.balls[j].y - self.balls[i].y);
+        self.constraints.push(DistanceConstraint::new(a, b, length));
+        true
+    }
+
+    pub fn rope(&mut self, from : RopeEnd, to : RopeEnd, length : Float, segments : usize, radius : Float, mass : Float) -> Result<usize, RopeError> {
+        //strings segments balls evenly along the line between the ends, each tied to the next by a link of at most length / (segments + 1)
+        //a rope longer than the gap between its ends sags once it falls, the balls collide with everything like any others
+        //returns the index of the rope in Space::ropes
+        //where each end is and how far it reaches around that, a point takes no room
+        let at = |end : RopeEnd| match end {
+            RopeEnd::Point(x, y) => Ok(((x, y), 0.0)),
+            RopeEnd::Ball(id) => self.get_ball(id).map(|ball| ((ball.x, ball.y), ball.radius)).ok_or(RopeError::RemovedEnd(id)),
+        };
+        let (((x1, y1), reach1), ((x2, y2), reach2)) = (at(from)?, at(to)?);
+        if segments == 0 {
+            return Err(RopeError::NoSegments);
+        }
+        let gap = (x2 - x1).hypot(y2 - y1);
+        if length < gap {
+            return Err(RopeError::TooShort(gap));
+        }
+        //the balls start in a straight line, so the slack only shows once they fall, packed closer than their diameter they would start out overlapping
+        //the first and last also start out overlapping a ball tied at their end if it is closer than both radii
+        let (count, link) = (segments, length / (segments + 1) as Float);
+        let spacing = gap / (count + 1) as Float;
+        if spacing < 2.0 * radius || spacing < radius + reach1.max(reach2) {
+            return Err(RopeError::Crowded(spacing));
+        }
+        let mut balls : Vec<BallId> = Vec::new();
+        for k in 1..=count {
+            let along = k as Float / (count + 1) as Float;
+            let id = self.new_ball_unchecked(x1 + (x2 - x1) * along, y1 + (y2 - y1) * along, 0.0, 0.0, radius, mass, 0.2, 0.2, [0.55, 0.4, 0.25, 1.0]);
+            if let Some(&previous) = balls.last() {
+                self.constraints.push(DistanceConstraint::max(previous, id, link));
+            }
+            balls.push(id);
+        }
+        for (end, ball) in [(from, balls[0]), (to, balls[count - 1])] {
+            match end {
+                RopeEnd::Point(x, y) => self.pivots.push(Pivot::max(ball, x, y, link)),
+                RopeEnd::Ball(id) => self.constraints.push(DistanceConstraint::max(id, ball, link)),
+            }
+        }
+        let points = std::iter::once(from).chain(balls.into_iter().map(RopeEnd::Ball)).chain(std::iter::once(to)).collect();
+        self.ropes.push(Rope { points, ends : (from, to) });
+        Ok(self.ropes.len() - 1)
+    }
+
+    pub fn pivot(&mut self, id : BallId, x : Float, y : Float) -> bool {
+        //hangs the ball from the point (x, y) at the distance it is from it now, returns false if it has been removed
+        let length = match self.get_ball(id) {
+            Some(ball) => (ball.x - x).hypot(ball.y - y),
+            None => return false,
+        };
+        self.pivots.push(Pivot::new(id, x, y, length));
+        true
+    }
+
+    fn integrate_numerically(&mut self, dt : Float) {
+        //steps each ball's state to the end of the tick, then lays its path for the tick along the cubic through both ends
+        //paths stay polynomials, so impacts within the tick are still solved exactly, a ball redirected by one follows its analytic path until next tick
+        if !self.integrator.is_numerical() {
+            return;
+        }
+        for i in 0..self.balls.len() {
+            let ball = &self.balls[i];
+            let (hold_x, hold_y) = (ball.rest_x.is_some(), ball.rest_y.is_some());
+            let (x_since, y_since) = (self.elapsed - ball.x_reftime, self.elapsed - ball.y_reftime);
+            let accel = |(x, y) : (Float, Float), (vx, vy) : (Float, Float), s : Float| {
+                let ax = ball.acceleration_at(&self.fa, x_since + s).0;
+                let ay = ball.acceleration_at(&self.fa, y_since + s).1;
+                let (fx, fy) = self.acceleration_field.as_ref().map_or((0.0, 0.0), |field| field(x, y, vx, vy, self.elapsed + s));
+                //a resting axis has its acceleration cancelled by the boundary
+                (if hold_x { 0.0 } else { ax + fx }, if hold_y { 0.0 } else { ay + fy })
+            };
+            let (p0, v0) = ((ball.x, ball.y), (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed)));
+            let (p1, v1) = self.integrator.step(p0, v0, dt, accel);
+            let path = |c : [Float; 4]| -> Box<dyn Function> { Box::new(Polynomial::from_coefficients(Var::T, self.time_units, self.space_units, &c)) };
+            let (x_path, y_path) = (path(integrator::hermite(p0.0, v0.0, p1.0, v1.0, dt)), path(integrator::hermite(p0.1, v0.1, p1.1, v1.1, dt)));
+            let ball = &mut self.balls[i];
+            if !hold_x {
+                ball.x_reftime = self.elapsed;
+                ball.cached_x_dyn_function = Some(x_path);
+            }
+            if !hold_y {
+                ball.y_reftime = self.elapsed;
+                ball.cached_y_dyn_function = Some(y_path);
+            }
+            ball.soft_update_unchecked();
+        }
+    }
+
+    fn advance_to(&mut self, t : Float) {
+        //moves every ball along its cached path to time t
+        self.elapsed = t;
+        for ball in &mut self.balls {
+            let (x, y) = ((ball.fx.closure)(self.elapsed - ball.x_reftime), (ball.fy.closure)(self.elapsed - ball.y_reftime));
+            ball.x = x.unwrap();
+            ball.y = y.unwrap();
+            ball.angle = ball.spin_refangle + ball.angular_velocity * (self.elapsed - ball.spin_reftime);
+        }
+    }
+
+    fn gap_polynomial(p : &Polynomial, scale : Float, offset : Float) -> Polynomial {
+        //scale * p + offset, a gap is positive while the objects are apart
+        let mut coefficients : Vec<Float> = p.coefficients().iter().map(|c| c * scale).collect();
+        if coefficients.is_empty() {
+            coefficients.push(0.0);
+        }
+        coefficients[0] += offset;
+        Polynomial::from_coefficients(p.var, p.var_units, p.final_units, &coefficients)
+    }
+
+    fn closing_time(gap : &Polynomial, h : Float, min_rate : Float) -> Option<Float> {
+        Self::closing_time_where(gap, h, min_rate, |_| true)
+    }
+
+    fn closing_time_where(gap : &Polynomial, h : Float, min_rate : Float, valid : impl Fn(Float) -> bool) -> Option<Float> {
+        //earliest time in [0, h] where the gap is at or below zero and still shrinking faster than min_rate
+        //valid rules out times where the gap doesn't describe an actual contact
+        let rate = gap.differentiated(gap.var).ok()?.to_polynomial()?;
+        if gap.eval(0.0) <= 0.0 && rate.eval(0.0) < -min_rate && valid(0.0) {
+            return Some(0.0);
+        }
+        gap.roots_within(0.0, h).into_iter().find(|s| rate.eval(*s) < -min_rate && valid(*s))
+    }
+
+    fn edge_closing_time(px : &Polynomial, py : &Polynomial, r : Float, edge : &Segment, h : Float) -> Option<Float> {
+        //a ball meets an edge either on its face, r from the edge's line while between its ends, or at one of its ends
+        let mut first : Option<Float> = None;
+        let mut keep = |s : Option<Float>| {
+            if let Some(s) = s {
+                if first.is_none_or(|f| s < f) {
+                    first = Some(s);
+                }
+            }
+        };
+        let (dx, dy) = edge.direction();
+        if edge.length() > 0.0 {
+            let (nx, ny) = (-dy, dx);
+            let across = Self::gap_polynomial(px, nx, -(nx * edge.x1 + ny * edge.y1)).sum(&Self::gap_polynomial(py, ny, 0.0)).ok()?;
+            let along = Self::gap_polynomial(px, dx, -(dx * edge.x1 + dy * edge.y1)).sum(&Self::gap_polynomial(py, dy, 0.0)).ok()?;
+            let side = if across.eval(0.0) < 0.0 { -1.0 } else { 1.0 }; //edges are two sided, the ball collides with whichever it is on
+            let gap = Self::gap_polynomial(&across, side, -r);
+            keep(Self::closing_time_where(&gap, h, CLOSING_SPEED_EPSILON, |s| (0.0..=edge.length()).contains(&along.eval(s))));
+        }
+        for (ex, ey) in [(edge.x1, edge.y1), (edge.x2, edge.y2)] {
+            let (gx, gy) = (Self::gap_polynomial(px, 1.0, -ex), Self::gap_polynomial(py, 1.0, -ey));
+            let distance2 = gx.product(&gx).ok()?.sum(&gy.product(&gy).ok()?).ok()?;
+            keep(Self::closing_time(&Self::gap_polynomial(&distance2, 1.0, -r * r), h, 2.0 * r * CLOSING_SPEED_EPSILON));
+        }
+        first
+    }
+
+    fn squared_distance((px, py) : (&Polynomial, &Polynomial), (qx, qy) : (&Polynomial, &Polynomial)) -> Option<Polynomial> {
+        //squared distance between two paths over time, none if their units don't line up
+        let (dx, dy) = (qx.difference(px).ok()?, qy.difference(py).ok()?);
+        dx.product(&dx).ok()?.sum(&dy.product(&dy).ok()?).ok()
+    }
+
+    fn swept_bounds(&self, paths : &[Option<(Polynomial, Polynomial)>], h : Float, grow : Float) -> Vec<Aabb> {
+        //boxes around everywhere each ball's path takes it over the next h, with its radius scaled by grow
+        paths.iter().zip(&self.balls).map(|(path, ball)| {
+            let r = ball.radius * grow;
+            match path {
+                Some((px, py)) => {
+                    let ((x1, x2), (y1, y2)) = (px.extremes_within(0.0, h), py.extremes_within(0.0, h));
+                    Aabb { x1 : x1 - r, y1 : y1 - r, x2 : x2 + r, y2 : y2 + r }
+                }
+                None => Aabb::around(ball.x, ball.y, r),
+            }
+        }).collect()
+    }
+
+    fn earliest_impact(&mut self, h : Float) -> Option<(Float, Impact)> {
+        //solves each ball's path polynomials against the boundaries and each other ball for the first contact within h
+        //balls whose paths aren't polynomials are skipped and left to the discrete pass
+        let paths : Vec<Option<(Polynomial, Polynomial)>> = self.balls.iter().map(|ball| ball.path_polynomials(self.elapsed)).collect();
+        //only pairs whose boxes swept over the interval overlap can meet
+        let sweeps = self.swept_bounds(&paths, h, 1.0);
+        let candidates = self.broad_phase.pairs(&sweeps);
+        let mut earliest : Option<(Float, Impact)> = None;
+        let mut consider = |s : Option<Float>, impact : Impact| {
+            if let Some(s) = s {
+                if earliest.is_none_or(|(e, _)| s < e) {
+                    earliest = Some((s, impact));
+                }
+            }
+        };
+        for (i, path) in paths.iter().enumerate() {
+            let (px, py) = match path {
+                Some(path) => path,
+                None => continue,
+            };
+            for boundary in Boundary::ALL {
+                let reach = match self.boundary_reach(i, boundary) {
+                    Some(reach) => reach,
+                    None => continue,
+                };
+                let offset = -(self.boundary_offset(boundary) + reach);
+                let (nx, ny) = boundary.normal();
+                let gap = if nx != 0.0 { Self::gap_polynomial(px, nx, offset) } else { Self::gap_polynomial(py, ny, offset) };
+                consider(Self::closing_time(&gap, h, CLOSING_SPEED_EPSILON), Impact::Boundary(i, boundary));
+            }
+        }
+        //obstacles are few and never move, so each ball's swept box is checked against every edge
+        for (k, edge) in self.obstacle_edges().iter().enumerate() {
+            let bounds = edge.bounds();
+            for i in 0..self.balls.len() {
+                if let (Some((px, py)), true) = (&paths[i], sweeps[i].overlaps(&bounds)) {
+                    consider(Self::edge_closing_time(px, py, self.balls[i].radius, edge, h), Impact::Edge(i, k));
+                }
+            }
+        }
+        for (i, j) in candidates {
+            let ((px, py), (qx, qy)) = match (&paths[i], &paths[j]) {
+                (Some(p), Some(q)) => (p, q),
+                _ => continue,
+            };
+            //a pair whose paths can't be combined is left to the discrete pass, the same as one without polynomial paths
+            let distance2 = match Self::squared_distance((px, py), (qx, qy)) {
+                Some(distance2) => distance2,
+                None => continue,
+            };
+            let reach = self.balls[i].radius + self.balls[j].radius;
+            consider(Self::closing_time(&Self::gap_polynomial(&distance2, 1.0, -reach * reach), h, 2.0 * reach * CLOSING_SPEED_EPSILON), Impact::Pair(i, j)); //the gap is in squared distance
+        }
+        earliest
+    }
+
+    fn resolve_impact(&mut self, impact : Impact, neighbours : &[Vec<BallId>]) {
+        match impact {
+            Impact::Boundary(i, boundary) => {
+                self.hit_boundary(i, boundary);
+            }
+            Impact::Pair(i, j) => {
+                let group = self.contact_group(i, j, neighbours);
+                self.solve_contacts(&group);
+            }
+            Impact::Edge(i, k) => {
+                let edge = self.obstacle_edges()[k];
+                self.bounce_edge(i, &edge);
+            }
+        }
+    }
+
+    fn resolve_overlaps(&mut self) {
+        //discrete collision handling at the current time, for whatever is touching now
+        //backwards, so balls absorbed along the way don't shift the ones still to check
+        for i in (0..self.balls.len()).rev() {
+            for boundary in Boundary::ALL {
+                if self.touches_boundary(i, boundary) && !self.hit_boundary(i, boundary) {
+                    break;
+                }
+            }
+        }
+        let edges = self.obstacle_edges();
+        for i in 0..self.balls.len() {
+            for edge in &edges {
+                if self.touches_edge(i, edge) {
+                    self.bounce_edge(i, edge);
+                }
+            }
+        }
+        let contacts = self.search_collision_pairs();
+        self.solve_contacts(&contacts);
+    }
+
+    fn relocate(&mut self, i : usize, x : Float, y : Float) {
+        //moves ball i without changing its velocity, re-anchoring its paths at the new position
+        //an axis resting on a boundary keeps its held position
+        let ball = &mut self.balls[i];
+        let (vx, vy) = (ball.get_vx(self.elapsed), ball.get_vy(self.elapsed));
+        let mut t = Recalculate::no_update();
+        if ball.rest_x.is_none() && x != ball.x {
+            ball.x = x;
+            t.val.0 = MaybeNew::Update(self.elapsed);
+        }
+        if ball.rest_y.is_none() && y != ball.y {
+            ball.y = y;
+            t.val.1 = MaybeNew::Update(self.elapsed);
+        }
+        ball.hard_update(&self.a, ball.x, ball.y, vx, vy, t);
+    }
+
+    fn correct_positions(&mut self) {
+        //collisions only change velocities, so overlaps left behind are pushed apart here (baumgarte style)
+        //each pass removes a fraction of every overlap beyond the slop, split between balls by inverse mass
+        //a ball resting on a boundary behind the push can't move, so the other ball takes the whole correction
+        if self.position_correction <= 0.0 || self.position_iterations == 0 {
+            return;
+        }
+        let pairs = self.search_collision_pairs();
+        let edges = self.obstacle_edges();
+        let mut positions : Vec<(Float, Float)> = self.balls.iter().map(|ball| (ball.x, ball.y)).collect();
+        for _ in 0..self.position_iterations {
+            let excess = |depth : Float| if depth > self.penetration_slop { self.position_correction * (depth - self.penetration_slop) } else { 0.0 };
+            for (i, ball) in self.balls.iter().enumerate() {
+                let (x, y) = &mut positions[i];
+                for boundary in Boundary::ALL {
+                    if self.get_boundary_mode(boundary) != BoundaryMode::Reflect {
+                        continue;
+                    }
+                    let (nx, ny) = boundary.normal();
+                    let push = excess(self.boundary_offset(boundary) + ball.radius - (nx * *x + ny * *y));
+                    (*x, *y) = (*x + nx * push, *y + ny * push);
+                }
+                for edge in &edges {
+                    let (cx, cy) = edge.closest_point(*x, *y);
+                    let d = (*x - cx).hypot(*y - cy);
+                    if d == 0.0 {
+                        continue;
+                    }
+                    let push = excess(ball.radius - d);
+                    (*x, *y) = (*x + (*x - cx) / d * push, *y + (*y - cy) / d * push);
+                }
+            }
+            for &(i, j) in &pairs {
+                let ((x1, y1), (x2, y2)) = (positions[i], positions[j]);
+                let d = (x2 - x1).hypot(y2 - y1);
+                let depth = self.balls[i].radius + self.balls[j].radius - d;
+                if depth <= self.penetration_slop || d == 0.0 {
+                    continue;
+                }
+                let (nx, ny) = ((x2 - x1) / d, (y2 - y1) / d); //from ball i to ball j
+                let inverse_mass = |k : usize, x : Float, y : Float| if self.balls[k].supported_along(x, y) { 0.0 } else { 1.0 / self.balls[k].mass };
+                let (w1, w2) = (inverse_mass(i, -nx, -ny), inverse_mass(j, nx, ny));
+                if w1 + w2 == 0.0 {
+                    continue;
+                }
+                let push = self.position_correction * (depth - self.penetration_slop) / (w1 + w2);
+                positions[i] = (x1 - nx * push * w1, y1 - ny * push * w1);
+                positions[j] = (x2 + nx * push * w2, y2 + ny * push * w2);
+            }
+        }
+        for (i, (x, y)) in positions.into_iter().enumerate() {
+            self.relocate(i, x, y);
+        }
+    }
+
+    fn substeps_for(&self, dt : Float) -> usize {
+        //the distance a ball can cover in dt is bounded by its speed and acceleration at the start of the tick
+        let fraction = match self.adaptive_substeps {
+            Some(fraction) if fraction > 0.0 => fraction,
+            _ => return 1,
+        };
+        self.balls.iter().map(|ball| {
+            let speed = ball.get_vx(self.elapsed).hypot(ball.get_vy(self.elapsed));
+            let accel = ball.get_ax(self.elapsed).hypot(ball.get_ay(self.elapsed));
+            let travel = speed * dt + 0.5 * accel * dt * dt;
+            (travel / (fraction * ball.radius)).ceil() as usize
+        }).max().unwrap_or(1).max(1)
+    }
+
+    pub fn get_substep_stats(&self) -> SubstepStats {
+        self.substep_stats
+    }
+
+    pub fn reset_substep_stats(&mut self) {
+        self.substep_stats = SubstepStats::default();
+    }
+
+    pub fn tick(&mut self, dt: Float) {
+        let wanted = self.substeps_for(dt);
+        let substeps = wanted.min(self.max_substeps.max(1));
+        let stats = &mut self.substep_stats;
+        stats.ticks += 1;
+        stats.substeps += substeps as u64;
+        stats.capped += (wanted > substeps) as u64;
+        stats.most = stats.most.max(substeps);
+        for _ in 0..substeps {
+            self.step(dt / substeps as Float);
+        }
+    }
+
+    fn step(&mut self, dt: Float) {
+        self.release_resting();
+        self.resting_friction(dt);
+        self.apply_drag(dt);
+        self.apply_pair_forces(dt);
+        self.apply_springs(dt);
+        self.integrate_numerically(dt);
+        let end = self.elapsed + dt;
+        if self.continuous {
+            //walk through the tick impact by impact, resolving each at the moment it happens
+            let mut impacts = 0;
+            let neighbours = self.contact_neighbours(dt);
+            while impacts < self.max_impacts_per_tick {
+                match self.earliest_impact(end - self.elapsed) {
+                    Some((s, impact)) => {
+                        self.advance_to(self.elapsed + s);
+                        self.resolve_impact(impact, &neighbours);
+                        impacts += 1;
+                    }
+                    None => break,
+                }
+            }
+        }
+        self.advance_to(end);
+        self.resolve_overlaps();
+        self.correct_positions();
+        self.enforce_constraints();
+    }
+
+    pub fn get_elapsed(&self) -> Float {
+        self.elapsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kinematics::Monomial;
+    use crate::kinematics::Polynomial;
+    use super::*;
+    fn uniform_space(ax : Float, ay : Float) -> Space {
+        let mps2 : Units = Unit::M.units() / Unit::S.units() / Unit::S.units();
+        Space::blank(
+            AccelxyFunction::IndependentFunctions(
+                Box::new(Polynomial::init(Var::T, Unit::S.units(), mps2, vec![Monomial::init(ax, mps2, 0)])),
+                Box::new(Polynomial::init(Var::T, Unit::S.units(), mps2, vec![Monomial::init(ay, mps2, 0)])),
+            )
+        )
+    }
+    #[test]
+    fn continuous_balls_dont_tunnel() {
+        //each ball covers 20m in the tick, far more than their combined diameters
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.x1 = -100.0;
+        myspace.x2 = 100.0;
+        myspace.new_ball_unchecked(-5.0, 5.0,  200.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked( 5.0, 5.0, -200.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.1);
+        assert!((myspace.balls[0].get_vx(myspace.get_elapsed()) + 200.0).abs() < 1e-2);
+        assert!((myspace.balls[1].get_vx(myspace.get_elapsed()) - 200.0).abs() < 1e-2);
+        assert!(myspace.balls[0].get_x() < myspace.balls[1].get_x());
+    }
+    #[test]
+    fn substeps_catch_fast_small_balls() {
+        //without continuous collision each ball covers three times the combined diameters in a tick and they pass straight through
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.continuous = false;
+        myspace.new_ball_unchecked(-0.3, 5.0,  100.0, 0.0, 0.1, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked( 0.3, 5.0, -100.0, 0.0, 0.1, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.adaptive_substeps = Some(0.5);
+        myspace.tick(1.0 / 165.0);
+        let (stats, t) = (myspace.get_substep_stats(), myspace.get_elapsed());
+        assert_eq!((stats.ticks, stats.substeps, stats.capped, stats.most), (1, 13, 0, 13));
+        assert!(myspace.balls[0].get_x() < myspace.balls[1].get_x());
+        assert!((myspace.balls[0].get_vx(t) + 100.0).abs() < 1e-2);
+        //capped, the tick is still split as far as allowed and the shortfall is counted
+        myspace.max_substeps = 4;
+        myspace.tick(1.0 / 165.0);
+        let stats = myspace.get_substep_stats();
+        assert_eq!((stats.ticks, stats.substeps, stats.capped, stats.most), (2, 17, 1, 13));
+        assert!((myspace.get_elapsed() - 2.0 / 165.0).abs() < 1e-6);
+        myspace.reset_substep_stats();
+        assert_eq!(myspace.get_substep_stats(), SubstepStats::default());
+    }
+    #[test]
+    fn continuous_floor_doesnt_tunnel() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.set_boundary_mode(Boundary::Ceiling, BoundaryMode::Open);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, -1000.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.05);
+        let ball = &myspace.balls[0];
+        assert!(ball.get_y() - ball.get_radius() >= myspace.floor - 1e-3);
+        assert!(ball.get_vy(myspace.get_elapsed()) > 0.0);
+    }
+    #[test]
+    fn unsolvable_pair_keeps_other_impacts() {
+        //the second ball's x path is in kilograms, so it can't be set against the first's, that pair alone is skipped
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.set_boundary_mode(Boundary::Ceiling, BoundaryMode::Open);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, -1000.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(1.8, 3.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let odd = &mut myspace.balls[1];
+        odd.cached_x_dyn_function = Some(Box::new(Polynomial::from_coefficients(Var::T, Unit::S.units(), Unit::KG.units(), &[1.8])));
+        odd.soft_update_unchecked();
+        myspace.tick(0.05);
+        //the floor is still hit mid tick, so the ball spends the rest of it rising instead of being found under the floor at the end
+        let ball = &myspace.balls[0];
+        assert!(ball.get_vy(myspace.get_elapsed()) > 0.0);
+        assert!(ball.get_y() > 40.0, "ball at {}", ball.get_y());
+    }
+    #[test]
+    fn broad_phases_match_brute_force() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.x1 = -100.0;
+        myspace.x2 = 100.0;
+        for k in 0..400 {
+            //a lattice slightly out of step with itself so neighbours overlap by varying amounts
+            let (x, y) = ((k % 20) as Float * 1.9 - 19.0, (k / 20) as Float * 1.7 + 1.0);
+            myspace.new_ball_unchecked(x, y, 0.0, 0.0, 0.8 + (k % 7) as Float * 0.05, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        }
+        let expected = myspace.search_collision_pairs();
+        assert!(!expected.is_empty());
+        myspace.broad_phase = Box::new(broadphase::SpatialGrid::new());
+        assert_eq!(myspace.search_collision_pairs(), expected);
+        myspace.broad_phase = Box::new(broadphase::SpatialGrid::with_cell_size(0.7));
+        assert_eq!(myspace.search_collision_pairs(), expected);
+        myspace.broad_phase = Box::new(broadphase::SweepAndPrune::new());
+        assert_eq!(myspace.search_collision_pairs(), expected);
+    }
+    #[test]
+    fn empty_space_ticks() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        assert!(myspace.search_collision_pairs().is_empty());
+        myspace.tick(0.01);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.01);
+    }
+    #[test]
+    fn bouncing_ball_comes_to_rest() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.x1 = -100.0;
+        myspace.x2 = 100.0;
+        myspace.new_ball_unchecked(0.0, 5.0, 3.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        for _ in 0..600 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let ball = &myspace.balls[0];
+        assert!(ball.is_resting());
+        assert_eq!(ball.get_y(), myspace.floor + ball.get_radius());
+        assert_eq!(ball.get_vy(myspace.get_elapsed()), 0.0);
+        //resting doesn't stop the ball rolling along the floor
+        assert!(ball.get_vx(myspace.get_elapsed()).abs() > 0.0);
+        for _ in 0..100 {
+            myspace.tick(1.0 / 165.0);
+            assert_eq!(myspace.balls[0].get_y(), myspace.floor + myspace.balls[0].get_radius());
+        }
+    }
+    #[test]
+    fn resting_ball_lifts_off() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.new_ball_unchecked(0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(1.0 / 165.0);
+        assert!(myspace.balls[0].is_resting());
+        myspace.set_acceleration(uniform_space(0.0, -GRAVITY_MPS2).a);
+        for _ in 0..10 {
+            myspace.tick(1.0 / 165.0);
+        }
+        assert!(!myspace.balls[0].is_resting());
+        assert!(myspace.balls[0].get_y() > 1.0);
+    }
+    #[test]
+    fn slow_contact_settles() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.new_ball_unchecked(0.0, 5.0,  0.4, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(2.1, 5.0, -0.1, 0.0, 1.0, 3.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        for _ in 0..100 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let t = myspace.get_elapsed();
+        let (v0, v1) = (myspace.balls[0].get_vx(t), myspace.balls[1].get_vx(t));
+        assert!((v0 - v1).abs() < 1e-5);
+        assert!((v0 + 3.0 * v1 - 0.1).abs() < 1e-5); //momentum kept
+    }
+    #[test]
+    fn pile_stays_piled() {
+        //two balls wedged between the walls with a third sitting in the notch between them
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.x1 = -2.0;
+        myspace.x2 = 2.0;
+        myspace.new_ball_unchecked(-1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.5, 0.5, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked( 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.5, 0.5, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked( 0.0, 1.0 + (3.0 as Float).sqrt(), 0.0, 0.0, 1.0, 1.0, 0.5, 0.5, [1.0,1.0,1.0,1.0]);
+        for _ in 0..1000 {
+            myspace.tick(1.0 / 165.0);
+        }
+        assert!((myspace.balls[0].get_y() - 1.0).abs() < 1e-4);
+        assert!((myspace.balls[1].get_y() - 1.0).abs() < 1e-4);
+        assert!((myspace.balls[2].get_y() - (1.0 + (3.0 as Float).sqrt())).abs() < 0.05);
+        assert!(myspace.balls[2].get_x().abs() < 0.05);
+    }
+    #[test]
+    fn overlaps_pushed_out() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.resting_threshold = 0.0;
+        myspace.new_ball_unchecked(-9.5, 5.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(3.0, 5.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(4.0, 5.0, 0.0, 0.0, 1.0, 3.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        for _ in 0..5 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let t = myspace.get_elapsed();
+        assert!(myspace.balls[0].get_x() - 1.0 >= myspace.x1 - myspace.penetration_slop - 1e-6);
+        let gap = myspace.balls[2].get_x() - myspace.balls[1].get_x();
+        assert!(gap >= 2.0 - myspace.penetration_slop - 1e-3);
+        //the lighter ball moved three times as far, and nothing was given any speed
+        assert!(((3.0 - myspace.balls[1].get_x()) - 3.0 * (myspace.balls[2].get_x() - 4.0)).abs() < 1e-3);
+        assert_eq!(myspace.balls[1].get_vx(t), 0.0);
+    }
+    #[test]
+    fn floor_bounce_starts_rolling() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.x1 = -100.0;
+        myspace.x2 = 100.0;
+        myspace.new_ball_unchecked(0.0, 3.0, 10.0, 0.0, 1.0, 2.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[0].set_shape(InertiaShape::SolidDisc);
+        for _ in 0..50 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let ball = &myspace.balls[0];
+        let vx = ball.get_vx(myspace.get_elapsed());
+        //no slip at the contact point, and a disc keeps two thirds of its speed when it grips
+        assert!((vx + ball.get_angular_velocity() * ball.get_radius()).abs() < 1e-3);
+        assert!((vx - 10.0 * 2.0 / 3.0).abs() < 1e-3);
+        assert!(ball.get_angle() < 0.0);
+    }
+    #[test]
+    fn glancing_collision_keeps_angular_momentum() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.new_ball_unchecked(-1.0, 5.0, 4.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let reach = 2.2 * kinematics::consts::FRAC_1_SQRT_2; //touching exactly, so both impulses act at one point
+        let id = myspace.new_ball_unchecked(-1.0 + reach, 5.0 + reach, -1.0, 0.0, 1.2, 2.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[1].set_shape(InertiaShape::HollowSphere);
+        myspace.spin_ball(id, 3.0);
+        let angular_momentum = |space : &Space| space.balls.iter().map(|b| {
+            let (vx, vy) = (b.get_vx(space.get_elapsed()), b.get_vy(space.get_elapsed()));
+            b.get_mass() * (b.get_x() * vy - b.get_y() * vx) + b.get_moment_of_inertia() * b.get_angular_velocity()
+        }).sum::<Float>();
+        let before = angular_momentum(&myspace);
+        myspace.exert_collision(0, 1);
+        assert!(myspace.balls[0].get_angular_velocity() != 0.0);
+        assert!((angular_momentum(&myspace) - before).abs() < 1e-3);
+    }
+    #[test]
+    fn sliding_ball_skids_into_rolling() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.x1 = -100.0;
+        myspace.x2 = 100.0;
+        myspace.new_ball_unchecked(0.0, 1.0, 5.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(20.0, 1.0, 5.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[1].set_friction(0.0, 0.0);
+        for _ in 0..165 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let t = myspace.get_elapsed();
+        let ball = &myspace.balls[0];
+        //a solid sphere rolls on with 1 / (1 + 2/5) of its sliding speed
+        assert!((ball.get_vx(t) - 5.0 / 1.4).abs() < 1e-2);
+        assert!((ball.get_vx(t) + ball.get_angular_velocity() * ball.get_radius()).abs() < 1e-2);
+        //without friction the other just slides
+        assert!((myspace.balls[1].get_vx(t) - 5.0).abs() < 1e-4);
+        assert_eq!(myspace.balls[1].get_angular_velocity(), 0.0);
+    }
+    #[test]
+    fn frictionless_collision_keeps_tangential_velocity() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.new_ball_unchecked(0.0, 5.0, 3.0, 2.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(2.0, 5.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[0].set_friction(0.0, 0.0);
+        myspace.exert_collision(0, 1);
+        let t = myspace.get_elapsed();
+        assert!((myspace.balls[0].get_vy(t) - 2.0).abs() < 1e-5);
+        assert!(myspace.balls[1].get_vy(t).abs() < 1e-5);
+        assert_eq!(myspace.balls[0].get_angular_velocity(), 0.0);
+    }
+    #[test]
+    fn newtons_cradle() {
+        //a ball strikes a touching row of four, within the tick the far end leaves with all of its speed and the rest stay put
+        //the row is added in both orders, so the outcome can't come from which pair happens to be solved first
+        for reversed in [false, true] {
+            for continuous in [true, false] {
+                let mut myspace = uniform_space(0.0, 0.0);
+                myspace.continuous = continuous;
+                let mut xs = vec![-2.0, 0.0, 2.0, 4.0, 6.0];
+                if reversed {
+                    xs.reverse();
+                }
+                for &x in &xs {
+                    myspace.new_ball_unchecked(x, 5.0, if x < 0.0 { 5.0 } else { 0.0 }, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+                }
+                myspace.tick(1.0 / 165.0);
+                let t = myspace.get_elapsed();
+                let mut row : Vec<(Float, Float)> = myspace.balls.iter().map(|ball| (ball.get_x(), ball.get_vx(t))).collect();
+                row.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                for (k, &(_, vx)) in row.iter().enumerate() {
+                    let expected = if k == 4 { 5.0 } else { 0.0 };
+                    assert!((vx - expected).abs() < 1e-3, "reversed {} continuous {} ball {} moving at {}", reversed, continuous, k, vx);
+                }
+            }
+        }
+    }
+    #[test]
+    fn ceiling_reflects() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, 100.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.1);
+        let ball = &myspace.balls[0];
+        //reaches the ceiling at y2 - r after 0.04s, then comes back down at half speed
+        assert!((ball.get_vy(myspace.get_elapsed()) + 50.0).abs() < 1e-3);
+        assert!((ball.get_y() - 6.0).abs() < 1e-3);
+    }
+    #[test]
+    fn absorbing_floor_deletes() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.set_boundary_mode(Boundary::Floor, BoundaryMode::Absorb);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, -1000.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(5.0, 5.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.05);
+        assert_eq!(myspace.balls.len(), 1);
+        assert_eq!(myspace.balls[0].get_x(), 5.0);
+    }
+    #[test]
+    fn absorbing_floor_deletes_from_middle() {
+        //two balls in the middle of the list are absorbed in the same tick, the ones after them still get their own impacts
+        for continuous in [true, false] {
+            let mut myspace = uniform_space(0.0, 0.0);
+            myspace.continuous = continuous;
+            myspace.set_boundary_mode(Boundary::Floor, BoundaryMode::Absorb);
+            let ids : Vec<BallId> = [(-8.0, 0.0, 0.0), (-4.0, 0.0, -1000.0), (0.0, 0.0, 0.0), (4.0, 0.0, -1000.0), (8.0, 100.0, 0.0)].iter().map(|&(x, vx, vy)| {
+                myspace.new_ball_unchecked(x, 5.0, vx, vy, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0])
+            }).collect();
+            myspace.tick(0.05);
+            let t = myspace.get_elapsed();
+            assert_eq!(myspace.ball_ids(), vec![ids[0], ids[2], ids[4]], "continuous {}", continuous);
+            assert_eq!((myspace.balls[0].get_x(), myspace.balls[1].get_x()), (-8.0, 0.0));
+            assert_eq!(myspace.get_ball(ids[4]).unwrap().get_vx(t), -100.0, "continuous {}", continuous);
+        }
+    }
+    #[test]
+    fn wrapping_boundaries() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        for boundary in Boundary::ALL {
+            myspace.set_boundary_mode(boundary, BoundaryMode::Wrap);
+        }
+        myspace.new_ball_unchecked(9.0, 5.0, 10.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, -30.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.4);
+        let t = myspace.get_elapsed();
+        //the first crosses the right side once, the second falls through the floor and out the ceiling twice
+        assert!((myspace.balls[0].get_x() + 7.0).abs() < 1e-3);
+        assert_eq!(myspace.balls[0].get_vx(t), 10.0);
+        assert!((myspace.balls[1].get_y() - 3.0).abs() < 1e-3);
+        assert_eq!(myspace.balls[1].get_vy(t), -30.0);
+    }
+    #[test]
+    fn open_ceiling_lets_balls_out() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.set_boundary_mode(Boundary::Ceiling, BoundaryMode::Open);
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, 100.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.1);
+        assert!((myspace.balls[0].get_y() - 15.0).abs() < 1e-3);
+    }
+    #[test]
+    fn ball_deflects_off_ramp() {
+        //a 45 degree ramp turns a straight drop into horizontal motion
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.obstacles.push(Obstacle::Segment(Segment::new(-5.0, 5.0, 5.0, -5.0)));
+        myspace.new_ball_unchecked(0.0, 5.0, 0.0, -10.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[0].set_friction(0.0, 0.0);
+        myspace.tick(0.5);
+        let (ball, t) = (&myspace.balls[0], myspace.get_elapsed());
+        assert!((ball.get_vx(t) - 10.0).abs() < 1e-3);
+        assert!(ball.get_vy(t).abs() < 1e-3);
+        assert!((ball.get_y() - (2.0 as Float).sqrt()).abs() < 1e-3);
+    }
+    #[test]
+    fn ball_rolls_down_ramp() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let ramp = Segment::new(-10.0, 8.0, 10.0, 0.0);
+        myspace.obstacles.push(Obstacle::Segment(ramp));
+        myspace.new_ball_unchecked(-6.0, 8.0, 0.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        for _ in 0..60 {
+            myspace.tick(1.0 / 165.0);
+            let ball = &myspace.balls[0];
+            let (cx, cy) = ramp.closest_point(ball.get_x(), ball.get_y());
+            assert!((ball.get_x() - cx).hypot(ball.get_y() - cy) > ball.get_radius() - 0.05);
+        }
+        let ball = &myspace.balls[0];
+        assert!(ball.get_x() > -5.0);
+        //friction with the ramp spins it clockwise as it goes down to the right
+        assert!(ball.get_angular_velocity() < 0.0);
+    }
+    #[test]
+    fn peg_splits_falling_balls() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let peg = obstacle::Polygon::regular(0.0, 4.0, 0.5, 6);
+        myspace.obstacles.push(Obstacle::Polygon(peg.clone()));
+        myspace.new_ball_unchecked(-0.6, 9.0, 0.0, 0.0, 0.5, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(0.6, 9.0, 0.0, 0.0, 0.5, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[0].set_friction(0.0, 0.0);
+        myspace.balls[1].set_friction(0.0, 0.0);
+        for _ in 0..80 {
+            myspace.tick(1.0 / 165.0);
+            for ball in &myspace.balls {
+                for edge in peg.edges() {
+                    let (cx, cy) = edge.closest_point(ball.get_x(), ball.get_y());
+                    assert!((ball.get_x() - cx).hypot(ball.get_y() - cy) > ball.get_radius() - 0.05);
+                }
+            }
+        }
+        let t = myspace.get_elapsed();
+        assert!(myspace.balls[0].get_vx(t) < 0.0);
+        assert!(myspace.balls[1].get_vx(t) > 0.0);
+    }
+    #[test]
+    fn directional_acceleration() {
+        //gravity pointing straight down as a magnitude and angle moves balls the same as independent axes
+        let mps2 : Units = Unit::M.units() / Unit::S.units() / Unit::S.units();
+        let mut tilted = Space::blank(AccelxyFunction::ParterFunctionVector(
+            Box::new(Polynomial::init(Var::T, Unit::S.units(), mps2, vec![Monomial::init(-GRAVITY_MPS2, mps2, 0)])),
+            Angle::new(-90.0),
+        ));
+        let mut plain = uniform_space(0.0, GRAVITY_MPS2);
+        for space in [&mut tilted, &mut plain] {
+            space.new_ball_unchecked(-3.0, 8.0, 4.0, 2.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+            space.new_ball_unchecked(3.0, 6.0, -1.0, 5.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+            for _ in 0..100 {
+                space.tick(1.0 / 165.0);
+            }
+        }
+        for (a, b) in tilted.balls.iter().zip(&plain.balls) {
+            assert!((a.get_x() - b.get_x()).abs() < 1e-3);
+            assert!((a.get_y() - b.get_y()).abs() < 1e-3);
+        }
+        //a thrust at 30 degrees with no other acceleration, starting from (1, 2) at (3, -1) m/s
+        let mut thrust = Space::blank(AccelxyFunction::ParterFunctionVector(
+            Box::new(Polynomial::init(Var::T, Unit::S.units(), mps2, vec![Monomial::init(4.0, mps2, 0)])),
+            Angle::new(30.0),
+        ));
+        thrust.new_ball_unchecked(1.0, 2.0, 3.0, -1.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        thrust.tick(0.5);
+        let (ax, ay) = (4.0 * (30.0 as Float).to_radians().cos(), 4.0 * (30.0 as Float).to_radians().sin());
+        let ball = &thrust.balls[0];
+        assert!((ball.get_x() - (1.0 + 3.0 * 0.5 + 0.5 * ax * 0.25)).abs() < 1e-4);
+        assert!((ball.get_y() - (2.0 - 1.0 * 0.5 + 0.5 * ay * 0.25)).abs() < 1e-4);
+        assert!((ball.get_vx(0.5) - (3.0 + ax * 0.5)).abs() < 1e-4);
+    }
+    #[test]
+    fn composite_acceleration() {
+        //gravity, a sideways wind and a tilted thrust combined match one uniform acceleration of their sum
+        let mps2 : Units = Unit::M.units() / Unit::S.units() / Unit::S.units();
+        let constant = |a : Float| -> Box<dyn Function> { Box::new(Polynomial::init(Var::T, Unit::S.units(), mps2, vec![Monomial::init(a, mps2, 0)])) };
+        let mut combined = Space::blank(AccelxyFunction::CompositeAcceleration(
+            Box::new(AccelxyFunction::IndependentFunctions(constant(0.0), constant(GRAVITY_MPS2))),
+            Box::new(AccelxyFunction::CompositeAcceleration(
+                Box::new(AccelxyFunction::IndependentFunctions(constant(3.0), constant(0.0))),
+                Box::new(AccelxyFunction::ParterFunctionVector(constant(2.0), Angle::new(90.0))),
+            )),
+        ));
+        let mut plain = uniform_space(3.0, GRAVITY_MPS2 + 2.0);
+        let ((ax, ay), (px, py)) = (combined.fa.at(0.0), plain.fa.at(0.0));
+        assert!((ax - px).abs() < 1e-5 && (ay - py).abs() < 1e-5);
+        for space in [&mut combined, &mut plain] {
+            space.new_ball_unchecked(-3.0, 8.0, 4.0, 2.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+            space.new_ball_unchecked(3.0, 6.0, -1.0, 5.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        }
+        let t = combined.get_elapsed();
+        assert!((combined.balls[0].get_vx(t) - 4.0).abs() < 1e-5);
+        assert!((combined.balls[0].get_vy(t) - 2.0).abs() < 1e-5);
+        //bounces and resting recalculate one axis at a time, the other has to keep its own constants
+        for _ in 0..200 {
+            combined.tick(1.0 / 165.0);
+            plain.tick(1.0 / 165.0);
+            for (a, b) in combined.balls.iter().zip(&plain.balls) {
+                assert!((a.get_x() - b.get_x()).abs() < 1e-3);
+                assert!((a.get_y() - b.get_y()).abs() < 1e-3);
+            }
+        }
+    }
+    #[test]
+    fn ball_force_scales_by_mass() {
+        let newtons : Units = Unit::KG.units() * Unit::M.units() / Unit::S.units() / Unit::S.units();
+        let constant = |f : Float| -> Box<dyn Function> { Box::new(Polynomial::init(Var::T, Unit::S.units(), newtons, vec![Monomial::init(f, newtons, 0)])) };
+        let mut myspace = uniform_space(0.0, 0.0);
+        let id = myspace.new_ball_unchecked(0.0, 5.0, 0.0, 0.0, 1.0, 2.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(0.0, 8.0, 0.0, 0.0, 1.0, 2.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        assert!(myspace.set_ball_force(id, Some(AccelxyFunction::IndependentFunctions(constant(10.0), constant(0.0)))));
+        //an acceleration passed as a force is turned away without touching the force already there
+        let mps2 = newtons / Unit::KG.units();
+        let accel = || -> Box<dyn Function> { Box::new(Polynomial::init(Var::T, Unit::S.units(), mps2, vec![Monomial::init(10.0, mps2, 0)])) };
+        assert!(!myspace.set_ball_force(id, Some(AccelxyFunction::IndependentFunctions(accel(), accel()))));
+        assert!(!myspace.set_ball_force(id, Some(AccelxyFunction::IndependentFunctions(constant(10.0), accel()))));
+        assert!(!myspace.set_ball_force(id, Some(AccelxyFunction::CompositeAcceleration(
+            Box::new(AccelxyFunction::IndependentFunctions(constant(10.0), constant(0.0))),
+            Box::new(AccelxyFunction::ParterFunctionVector(accel(), Angle::new(0.0))),
+        ))));
+        myspace.tick(0.5);
+        let t = myspace.get_elapsed();
+        assert!((myspace.balls[0].get_x() - 0.5 * 5.0 * 0.25).abs() < 1e-4);
+        assert!((myspace.balls[0].get_vx(t) - 5.0 * 0.5).abs() < 1e-4);
+        assert_eq!(myspace.balls[1].get_x(), 0.0);
+    }
+    #[test]
+    fn thrust_lifts_resting_ball() {
+        let newtons : Units = Unit::KG.units() * Unit::M.units() / Unit::S.units() / Unit::S.units();
+        let thrust = |f : Float| AccelxyFunction::ParterFunctionVector(Box::new(Polynomial::init(Var::T, Unit::S.units(), newtons, vec![Monomial::init(f, newtons, 0)])), Angle::new(90.0));
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let id = myspace.new_ball_unchecked(0.0, 1.0, 0.0, 0.0, 1.0, 3.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(1.0 / 165.0);
+        assert!(myspace.balls[0].is_resting());
+        //exactly the ball's weight leaves it sitting there, more lifts it off the floor
+        myspace.set_ball_force(id, Some(thrust(-GRAVITY_MPS2 * 3.0)));
+        for _ in 0..10 {
+            myspace.tick(1.0 / 165.0);
+        }
+        assert!((myspace.balls[0].get_y() - 1.0).abs() < 1e-3);
+        myspace.set_ball_force(id, Some(thrust(-GRAVITY_MPS2 * 6.0)));
+        for _ in 0..10 {
+            myspace.tick(1.0 / 165.0);
+        }
+        assert!(!myspace.balls[0].is_resting());
+        assert!(myspace.balls[0].get_y() > 1.1);
+        assert!(myspace.balls[0].get_vy(myspace.get_elapsed()) > 0.0);
+    }
+    #[test]
+    fn linear_drag_decays_exponentially() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.x2 = 1000.0;
+        myspace.new_ball_unchecked(0.0, 5.0, 20.0, 0.0, 1.0, 2.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[0].set_drag(3.0, 0.0);
+        for _ in 0..165 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let expected = 20.0 * (-(1.5 as Float)).exp();
+        assert!((myspace.balls[0].get_vx(myspace.get_elapsed()) - expected).abs() < 1e-3);
+    }
+    #[test]
+    fn drag_reaches_terminal_velocity() {
+        //a 2kg ball, terminal velocity is where drag balances the weight
+        for (linear, quadratic, terminal) in [(8.0, 0.0, 2.0 * GRAVITY_MPS2 / 8.0), (0.0, 0.5, -(-2.0 * GRAVITY_MPS2 / (0.5 as Float)).sqrt())] {
+            let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+            myspace.floor = -1.0e5;
+            myspace.new_ball_unchecked(0.0, 5.0, 0.0, 0.0, 1.0, 2.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+            myspace.balls[0].set_drag(linear, quadratic);
+            for k in 1..=660 {
+                myspace.tick(1.0 / 165.0);
+                let t = myspace.get_elapsed();
+                //linear drag follows v = terminal * (1 - e^(-kt)) on the way there too
+                if linear > 0.0 && k == 50 {
+                    let expected = terminal * -(-linear / 2.0 * t).exp_m1();
+                    assert!((myspace.balls[0].get_vy(t) - expected).abs() < 1e-2);
+                }
+            }
+            let vy = myspace.balls[0].get_vy(myspace.get_elapsed());
+            assert!((vy - terminal).abs() < 1e-2);
+        }
+    }
+    #[test]
+    fn drag_doesnt_lift_resting_balls() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.new_ball_unchecked(0.0, 1.0, 5.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.balls[0].set_drag(0.5, 0.1);
+        for _ in 0..100 {
+            myspace.tick(1.0 / 165.0);
+            assert!(myspace.balls[0].is_resting());
+        }
+        assert!(myspace.balls[0].get_vx(myspace.get_elapsed()).abs() < 5.0);
+    }
+    fn open_space() -> Space {
+        let mut myspace = uniform_space(0.0, 0.0);
+        (myspace.x1, myspace.x2, myspace.floor, myspace.y2) = (-100.0, 100.0, -100.0, 100.0);
+        myspace
+    }
+    #[test]
+    fn circular_orbit() {
+        let mut myspace = open_space();
+        myspace.pair_force = PairForce::Gravity(1.0);
+        myspace.softening = 0.0;
+        //a light moon at the circular orbit speed sqrt(G M / r) around a heavy planet
+        let (mass, r) = (1000.0 as Float, 5.0 as Float);
+        let v = (mass / r).sqrt();
+        myspace.new_ball_unchecked(0.0, 0.0, 0.0, 0.0, 1.0, mass, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(r, 0.0, 0.0, v, 0.2, 0.001, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let period = kinematics::consts::TAU * r / v;
+        let ticks = (period * 165.0).round() as usize;
+        for _ in 0..ticks {
+            myspace.tick(1.0 / 165.0);
+            let (planet, moon) = (&myspace.balls[0], &myspace.balls[1]);
+            let distance = (moon.get_x() - planet.get_x()).hypot(moon.get_y() - planet.get_y());
+            assert!((distance - r).abs() < 0.05 * r);
+        }
+        //back around to where it started
+        let moon = &myspace.balls[1];
+        assert!((moon.get_x() - r).abs() < 0.1 && moon.get_y().abs() < 0.2);
+    }
+    #[test]
+    fn charges_attract_and_repel() {
+        for (q1, q2, apart) in [(1.0, 1.0, true), (1.0, -1.0, false)] {
+            let mut myspace = open_space();
+            myspace.pair_force = PairForce::Coulomb(10.0);
+            myspace.new_ball_unchecked(-3.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+            myspace.new_ball_unchecked(3.0, 0.0, 0.0, 0.0, 0.5, 2.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+            myspace.new_ball_unchecked(0.0, 10.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]); //uncharged, so unmoved
+            myspace.balls[0].set_charge(q1);
+            myspace.balls[1].set_charge(q2);
+            for _ in 0..20 {
+                myspace.tick(1.0 / 165.0);
+            }
+            let t = myspace.get_elapsed();
+            let (b0, b1) = (&myspace.balls[0], &myspace.balls[1]);
+            assert_eq!(b0.get_vx(t) < 0.0, apart);
+            //equal and opposite forces keep the total momentum at zero
+            assert!((b0.get_vx(t) * b0.get_mass() + b1.get_vx(t) * b1.get_mass()).abs() < 1e-4);
+            assert_eq!(myspace.balls[2].get_vx(t), 0.0);
+            assert_eq!(myspace.balls[2].get_vy(t), 0.0);
+        }
+    }
+    #[test]
+    fn pair_force_methods_agree() {
+        let mut spaces : Vec<Space> = [PairForceMethod::Exact, PairForceMethod::Cutoff(500.0), PairForceMethod::BarnesHut(0.0)].into_iter().map(|method| {
+            let mut myspace = open_space();
+            myspace.pair_force = PairForce::Gravity(1.0);
+            myspace.pair_force_method = method;
+            myspace.broad_phase = Box::new(broadphase::SweepAndPrune::new());
+            for k in 0..30 {
+                myspace.new_ball_unchecked((k % 6) as Float * 10.0 - 25.0, (k / 6) as Float * 10.0 - 20.0, 0.0, 0.0, 0.5, 5.0 + k as Float, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+            }
+            myspace
+        }).collect();
+        for myspace in &mut spaces {
+            for _ in 0..10 {
+                myspace.tick(1.0 / 165.0);
+            }
+        }
+        for other in &spaces[1..] {
+            for (a, b) in spaces[0].balls.iter().zip(&other.balls) {
+                assert!((a.get_x() - b.get_x()).abs() < 1e-4 && (a.get_y() - b.get_y()).abs() < 1e-4);
+            }
+        }
+        //a short cutoff leaves the far apart balls alone
+        let mut myspace = open_space();
+        myspace.pair_force = PairForce::Gravity(1.0);
+        myspace.pair_force_method = PairForceMethod::Cutoff(5.0);
+        myspace.new_ball_unchecked(-10.0, 0.0, 0.0, 0.0, 0.5, 100.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.new_ball_unchecked(10.0, 0.0, 0.0, 0.0, 0.5, 100.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(1.0 / 165.0);
+        assert_eq!(myspace.balls[0].get_vx(myspace.get_elapsed()), 0.0);
+    }
+    #[test]
+    fn spring_oscillates() {
+        //two unit masses on a spring have a reduced mass of 1/2, so they swing between 6 and 2 apart with a period of 2 pi sqrt(1/2 / k)
+        let mut myspace = open_space();
+        let a = myspace.new_ball_unchecked(-3.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let b = myspace.new_ball_unchecked( 3.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.springs.push(Spring::new(a, b, 4.0, 10.0, 0.0));
+        let period = kinematics::consts::TAU * (0.5 as Float / 10.0).sqrt();
+        let (mut closest, mut closest_at, mut farthest) = (Float::INFINITY, 0.0, 0.0 as Float);
+        for _ in 0..(1.5 * period * 165.0) as usize {
+            myspace.tick(1.0 / 165.0);
+            let d = myspace.balls[1].get_x() - myspace.balls[0].get_x();
+            if d < closest && myspace.get_elapsed() < period {
+                (closest, closest_at) = (d, myspace.get_elapsed());
+            }
+            if myspace.get_elapsed() > period / 2.0 {
+                farthest = farthest.max(d);
+            }
+        }
+        assert!((closest - 2.0).abs() < 0.05, "closest {}", closest);
+        assert!((farthest - 6.0).abs() < 0.05, "farthest {}", farthest);
+        assert!((closest_at - period / 2.0).abs() < 2.0 / 165.0);
+        assert!((myspace.balls[0].get_x() + myspace.balls[1].get_x()).abs() < 1e-3);
+    }
+    #[test]
+    fn rod_holds_spinning_pair() {
+        //one end is flung sideways, the pair spins about its center of mass while the center drifts at half the speed
+        let mut myspace = open_space();
+        let a = myspace.new_ball_unchecked(0.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let b = myspace.new_ball_unchecked(4.0, 0.0, 0.0, 4.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.constraints.push(DistanceConstraint::new(a, b, 4.0));
+        for _ in 0..165 {
+            myspace.tick(1.0 / 165.0);
+            let (a, b) = (&myspace.balls[0], &myspace.balls[1]);
+            assert!(((b.get_x() - a.get_x()).hypot(b.get_y() - a.get_y()) - 4.0).abs() < 1e-3);
+        }
+        let (a, b) = (&myspace.balls[0], &myspace.balls[1]);
+        assert!(((a.get_x() + b.get_x()) / 2.0 - 2.0).abs() < 0.05);
+        assert!(((a.get_y() + b.get_y()) / 2.0 - 2.0).abs() < 0.05);
+    }
+    #[test]
+    fn pendulum_period() {
+        //small swings take 2 pi sqrt(l / g) with a correction for the amplitude, g read off the space's own acceleration
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let g = -myspace.fa.at(0.0).1;
+        let (length, swing) = (5.0 as Float, 0.1 as Float);
+        let id = myspace.new_ball_unchecked(length * swing.sin(), 8.0 - length * swing.cos(), 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        assert!(myspace.pivot(id, 0.0, 8.0));
+        let expected = kinematics::consts::TAU * (length / g).sqrt() * (1.0 + swing * swing / 16.0);
+        let dt = 1.0 / 165.0;
+        let (mut crossings, mut last) = (Vec::new(), myspace.balls[0].get_x());
+        while crossings.len() < 3 && myspace.get_elapsed() < 10.0 {
+            myspace.tick(dt);
+            let x = myspace.balls[0].get_x();
+            if x.signum() != last.signum() {
+                crossings.push(myspace.get_elapsed() - dt + dt * last / (last - x));
+            }
+            last = x;
+        }
+        let period = crossings[2] - crossings[0];
+        assert!((period - expected).abs() < 0.01 * expected, "swung in {} instead of {}", period, expected);
+    }
+    #[test]
+    fn double_pendulum_keeps_lengths() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let a = myspace.new_ball_unchecked(3.0, 8.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let b = myspace.new_ball_unchecked(6.0, 8.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.pivot(a, 0.0, 8.0);
+        myspace.pin(a, b);
+        for _ in 0..330 {
+            myspace.tick(1.0 / 165.0);
+            let (a, b) = (&myspace.balls[0], &myspace.balls[1]);
+            assert!((a.get_x().hypot(a.get_y() - 8.0) - 3.0).abs() < 1e-3);
+            assert!(((b.get_x() - a.get_x()).hypot(b.get_y() - a.get_y()) - 3.0).abs() < 1e-3);
+        }
+        //released level, rods only take energy out, so neither ball climbs above where it started
+        assert!(myspace.balls.iter().all(|ball| ball.get_y() <= 8.0 + 1e-3));
+    }
+    #[test]
+    fn rope_sags_and_catches_balls() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let rope = myspace.rope(RopeEnd::Point(-8.0, 6.0), RopeEnd::Point(8.0, 6.0), 17.0, 15, 0.4, 0.2).unwrap();
+        assert_eq!((myspace.balls.len(), myspace.ropes[rope].points.len()), (15, 17));
+        assert_eq!((myspace.constraints.len(), myspace.pivots.len()), (14, 2));
+        myspace.new_ball_unchecked(0.0, 9.0, 0.0, 0.0, 1.0, 1.0, 0.5, 0.5, [1.0,1.0,1.0,1.0]);
+        for _ in 0..330 {
+            myspace.tick(1.0 / 165.0);
+        }
+        //the iterative solve leaves links within a few percent of their length, even with a ball lying in the middle
+        let link = 17.0 / 16.0;
+        let points : Vec<(Float, Float)> = myspace.ropes[rope].points.iter().map(|&point| match point {
+            RopeEnd::Point(x, y) => (x, y),
+            RopeEnd::Ball(id) => myspace.get_ball(id).map(|ball| (ball.get_x(), ball.get_y())).unwrap(),
+        }).collect();
+        for pair in points.windows(2) {
+            assert!((pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1) < link * 1.03);
+        }
+        let lowest = points.iter().map(|point| point.1).fold(Float::INFINITY, Float::min);
+        let ball = &myspace.balls[15];
+        assert!(lowest < 6.0 - 1.0, "the rope should sag, lowest point at {}", lowest);
+        assert!(ball.get_y() > lowest && ball.get_y() < 6.0 && ball.get_x().abs() < 2.0, "the ball should rest in the rope, it is at ({}, {})", ball.get_x(), ball.get_y());
+    }
+    #[test]
+    fn rope_rejects_crowded_balls() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let (from, to) = (RopeEnd::Point(-1.0, 6.0), RopeEnd::Point(1.0, 6.0));
+        //ten balls across a gap of 2 would start 2 / 11 apart, well inside each other
+        assert_eq!(myspace.rope(from, to, 12.0, 10, 0.4, 0.2), Err(RopeError::Crowded(2.0 / 11.0)));
+        assert_eq!(myspace.rope(from, to, 12.0, 0, 0.4, 0.2), Err(RopeError::NoSegments));
+        assert_eq!(myspace.rope(from, to, 1.5, 1, 0.4, 0.2), Err(RopeError::TooShort(2.0)));
+        assert!(myspace.balls.is_empty() && myspace.constraints.is_empty() && myspace.pivots.is_empty() && myspace.ropes.is_empty());
+        assert_eq!(myspace.rope(from, to, 2.5, 1, 0.4, 0.2), Ok(0));
+        //a big ball at the end leaves no room for the first segment, though the segments have room between themselves
+        let anchor = myspace.new_ball_unchecked(-6.0, 6.0, 0.0, 0.0, 2.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        assert_eq!(myspace.rope(RopeEnd::Ball(anchor), RopeEnd::Point(2.0, 6.0), 10.0, 3, 0.4, 0.2), Err(RopeError::Crowded(2.0)));
+        assert_eq!(myspace.rope(RopeEnd::Point(2.0, 6.0), RopeEnd::Ball(anchor), 10.0, 3, 0.4, 0.2), Err(RopeError::Crowded(2.0)));
+        assert_eq!(myspace.rope(RopeEnd::Ball(anchor), RopeEnd::Point(6.0, 6.0), 14.0, 4, 0.4, 0.2), Ok(1));
+    }
+    #[test]
+    fn rope_keeps_its_balls_when_an_end_goes() {
+        //the ball the rope is tied to goes, the segment next to it is still part of the rope rather than its new end
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        let anchor = myspace.new_ball_unchecked(-4.0, 6.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        let rope = myspace.rope(RopeEnd::Ball(anchor), RopeEnd::Point(4.0, 6.0), 9.0, 3, 0.4, 0.2).unwrap();
+        let segments : Vec<BallId> = myspace.ropes[rope].segments().collect();
+        assert_eq!(segments, myspace.ball_ids()[1..]);
+        myspace.remove_ball(anchor);
+        assert_eq!(myspace.ropes[rope].segments().collect::<Vec<BallId>>(), segments);
+        assert_eq!(myspace.ropes[rope].points.len(), 4);
+    }
+    #[test]
+    fn ball_ids_survive_removal() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        let ids : Vec<BallId> = (0..4).map(|k| myspace.new_ball_unchecked(-6.0 + 4.0 * k as Float, 5.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0])).collect();
+        assert!(myspace.remove_ball(ids[1]).is_some_and(|ball| ball.get_x() == -2.0));
+        assert!(myspace.get_ball(ids[1]).is_none() && myspace.remove_ball(ids[1]).is_none());
+        //nothing can be spun, pinned or tied to a removed ball
+        assert!(!myspace.spin_ball(ids[1], 1.0) && !myspace.set_ball_force(ids[1], None) && !myspace.pin(ids[0], ids[1]) && !myspace.pivot(ids[1], 0.0, 8.0));
+        assert_eq!(myspace.rope(RopeEnd::Ball(ids[1]), RopeEnd::Point(0.0, 8.0), 4.0, 3, 0.2, 0.1), Err(RopeError::RemovedEnd(ids[1])));
+        assert!(myspace.balls.len() == 3 && myspace.constraints.is_empty() && myspace.pivots.is_empty());
+        assert_eq!((myspace.index_of(ids[2]), myspace.get_ball(ids[3]).map(|ball| ball.get_x())), (Some(1), Some(6.0)));
+        //the freed slot is reused, the old handle still doesn't resolve to the new ball
+        let new = myspace.new_ball_unchecked(0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        assert_ne!(new, ids[1]);
+        assert!(myspace.get_ball(ids[1]).is_none());
+        //removing while walking the ids leaves the rest reachable
+        for id in myspace.ball_ids() {
+            if myspace.get_ball(id).is_some_and(|ball| ball.get_x() >= 0.0) {
+                myspace.remove_ball(id);
+            }
+        }
+        assert_eq!(myspace.ball_ids(), vec![ids[0]]);
+        myspace.get_ball_mut(ids[0]).unwrap().set_charge(2.0);
+        assert_eq!(myspace.balls[0].get_charge(), 2.0);
+    }
+    #[test]
+    fn builder_sets_everything() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        let id = myspace.new_ball(BallBuilder::new().position(2.0, 4.0).velocity(-1.0, 3.0).radius(0.5).mass(2.0).material(Material::Ice).spin(4.0).charge(-1.0).drag(0.1, 0.0).shape(InertiaShape::SolidDisc)).unwrap();
+        let (ball, t) = (myspace.get_ball(id).unwrap(), myspace.get_elapsed());
+        assert_eq!((ball.get_x(), ball.get_y(), ball.get_vx(t), ball.get_vy(t)), (2.0, 4.0, -1.0, 3.0));
+        assert_eq!((ball.get_radius(), ball.get_mass(), ball.get_charge(), ball.get_linear_drag()), (0.5, 2.0, -1.0, 0.1));
+        assert_eq!(((ball.get_ground_bounce(), ball.get_free_bounce()), (ball.get_static_friction(), ball.get_kinetic_friction())), (Material::Ice.bounce(), Material::Ice.friction()));
+        assert_eq!((ball.get_angular_velocity(), ball.get_shape()), (4.0, InertiaShape::SolidDisc));
+        //anything left unset keeps the builder's default, a unit ball with the default friction
+        let id = myspace.new_ball(BallBuilder::new().position(-5.0, 5.0).bounce(0.5, 0.5)).unwrap();
+        let ball = myspace.get_ball(id).unwrap();
+        assert_eq!((ball.get_radius(), ball.get_mass(), ball.get_ground_bounce(), ball.get_static_friction()), (1.0, 1.0, 0.5, DEFAULT_STATIC_FRICTION));
+    }
+    #[test]
+    fn builder_rejects_bad_balls() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        let ball = BallBuilder::new().position(0.0, 5.0);
+        assert_eq!(myspace.new_ball(ball.radius(-1.0)), Err(BallError::NonPositiveRadius(-1.0)));
+        assert_eq!(myspace.new_ball(ball.mass(0.0)), Err(BallError::NonPositiveMass(0.0)));
+        assert_eq!(myspace.new_ball(ball.velocity(Float::NAN, 0.0)), Err(BallError::NotFinite("vx")));
+        assert_eq!(myspace.new_ball(ball.bounce(0.5, -0.1)), Err(BallError::NegativeBounce(-0.1)));
+        assert_eq!(myspace.new_ball(ball.bounce(1.1, 0.5)), Err(BallError::GainingBounce(1.1)));
+        assert_eq!(myspace.new_ball(ball.friction(-0.2, 0.1)), Err(BallError::NegativeFriction(-0.2)));
+        assert_eq!(myspace.new_ball(ball.drag(0.0, -1.0)), Err(BallError::NegativeDrag(-1.0)));
+        assert_eq!(myspace.new_ball(ball.position(12.0, 5.0)), Err(BallError::OutsideSpace(12.0, 5.0)));
+        assert_eq!(myspace.new_ball(ball.position(0.0, 0.5)), Err(BallError::OutsideSpace(0.0, 0.5)));
+        assert!(myspace.balls.is_empty());
+        assert!(myspace.new_ball(ball).is_ok());
+    }
+    #[test]
+    fn builder_allows_crossing_soft_sides() {
+        let mut myspace = uniform_space(0.0, 0.0);
+        myspace.set_boundary_mode(Boundary::Ceiling, BoundaryMode::Open);
+        myspace.set_boundary_mode(Boundary::Left, BoundaryMode::Wrap);
+        let ball = BallBuilder::new().position(0.0, 5.0);
+        assert!(myspace.new_ball(ball.position(0.0, 9.5)).is_ok());
+        assert!(myspace.new_ball(ball.position(-9.5, 5.0)).is_ok());
+        assert_eq!(myspace.new_ball(ball.position(9.5, 5.0)), Err(BallError::OutsideSpace(9.5, 5.0)));
+        myspace.set_boundary_mode(Boundary::Right, BoundaryMode::Absorb);
+        assert_eq!(myspace.new_ball(ball.position(9.5, 5.0)), Err(BallError::OutsideSpace(9.5, 5.0)));
+        assert_eq!(myspace.balls.len(), 2);
+        //nothing left to bounce between, the ceiling is open and the right side absorbs
+        assert!(myspace.new_ball(ball.bounce(1.1, 0.5)).is_ok());
+    }
+    #[test]
+    fn late_balls_start_where_placed() {
+        //paths are timed from when the ball is added, not from the start of the run
+        let mut myspace = uniform_space(0.0, 0.0);
+        for _ in 0..165 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let id = myspace.new_ball_unchecked(0.0, 5.0, 2.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        myspace.tick(0.5);
+        assert!((myspace.get_ball(id).unwrap().get_x() - 1.0).abs() < 1e-4);
+    }
+    #[test]
+    fn links_follow_absorbed_balls() {
+        let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+        myspace.set_boundary_mode(Boundary::Floor, BoundaryMode::Absorb);
+        let ids = [(0.0, 1.2, -50.0), (-3.0, 8.0, 0.0), (3.0, 8.0, 0.0)].map(|(x, y, vy)| myspace.new_ball_unchecked(x, y, 0.0, vy, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]));
+        myspace.springs.push(Spring::new(ids[1], ids[2], 4.0, 10.0, 0.0));
+        myspace.constraints.push(DistanceConstraint::new(ids[0], ids[1], 7.0));
+        myspace.pivots.push(Pivot::max(ids[0], 0.0, 9.0, 10.0));
+        myspace.ropes.push(Rope { points : vec![RopeEnd::Point(0.0, 9.0), RopeEnd::Ball(ids[0]), RopeEnd::Ball(ids[1])], ends : (RopeEnd::Point(0.0, 9.0), RopeEnd::Ball(ids[1])) });
+        myspace.tick(1.0 / 165.0);
+        assert_eq!(myspace.ball_ids(), vec![ids[1], ids[2]]);
+        assert_eq!(myspace.springs, vec![Spring::new(ids[1], ids[2], 4.0, 10.0, 0.0)]);
+        assert!(myspace.constraints.is_empty() && myspace.pivots.is_empty());
+        assert_eq!(myspace.ropes[0].points, vec![RopeEnd::Point(0.0, 9.0), RopeEnd::Ball(ids[1])]);
+        //the spring still pulls its own balls together now they sit one index down
+        let t = myspace.get_elapsed();
+        assert!(myspace.get_ball(ids[1]).unwrap().get_vx(t) > 0.0 && myspace.get_ball(ids[2]).unwrap().get_vx(t) < 0.0);
+    }
+    #[test]
+    fn integrators_follow_constant_gravity() {
+        //a ball thrown across open space against the exact polynomial, verlet and rk4 are exact for constant acceleration
+        let exact = |t : Float| (-50.0 + 30.0 * t, 20.0 * t + 0.5 * GRAVITY_MPS2 * t * t);
+        //semi-implicit euler drifts by g dt t / 2, first order in the tick length
+        let euler_drift = 0.5 * -GRAVITY_MPS2 / 165.0 * 2.0;
+        for (integrator, tolerance) in [(Integrator::SemiImplicitEuler, euler_drift + 0.05), (Integrator::VelocityVerlet, 2e-2), (Integrator::Rk4, 2e-2)] {
+            let mut myspace = open_space();
+            myspace.floor = -1.0e4;
+            myspace.y2 = 1.0e4;
+            myspace.set_acceleration(uniform_space(0.0, GRAVITY_MPS2).a);
+            myspace.integrator = integrator;
+            myspace.new_ball_unchecked(-50.0, 0.0, 30.0, 20.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+            for _ in 0..330 {
+                myspace.tick(1.0 / 165.0);
+            }
+            let t = myspace.get_elapsed();
+            let (x, y) = exact(t);
+            let ball = &myspace.balls[0];
+            assert!((ball.get_x() - x).abs() < tolerance && (ball.get_y() - y).abs() < tolerance, "{:?} at ({}, {}) instead of ({}, {})", integrator, ball.get_x(), ball.get_y(), x, y);
+            assert!((ball.get_vy(t) - (20.0 + GRAVITY_MPS2 * t)).abs() < tolerance);
+        }
+    }
+    #[test]
+    fn numerical_balls_still_settle() {
+        for integrator in [Integrator::SemiImplicitEuler, Integrator::VelocityVerlet, Integrator::Rk4] {
+            let mut myspace = uniform_space(0.0, GRAVITY_MPS2);
+            myspace.integrator = integrator;
+            myspace.new_ball_unchecked(0.0, 5.0, 0.0, 0.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+            for _ in 0..600 {
+                myspace.tick(1.0 / 165.0);
+                assert!(myspace.balls[0].get_y() > 1.0 - 0.05);
+            }
+            assert!(myspace.balls[0].is_resting(), "{:?}", integrator);
+        }
+    }
+    #[test]
+    fn integrators_follow_position_dependent_field() {
+        //a spring pulling toward the origin, which the analytic path can't see
+        for integrator in [Integrator::SemiImplicitEuler, Integrator::VelocityVerlet, Integrator::Rk4] {
+            let mut myspace = open_space();
+            myspace.integrator = integrator;
+            myspace.acceleration_field = Some(Box::new(|x, y, _, _, _| (-4.0 * x, -4.0 * y)));
+            myspace.new_ball_unchecked(10.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+            //half a period of x = 10 cos(2t)
+            let ticks = (kinematics::consts::FRAC_PI_2 * 165.0).round() as usize;
+            for _ in 0..ticks {
+                myspace.tick(1.0 / 165.0);
+            }
+            let expected = 10.0 * (2.0 * myspace.get_elapsed()).cos();
+            assert!((myspace.balls[0].get_x() - expected).abs() < 0.1, "{:?}", integrator);
+        }
+    }
+    #[test]
+    fn cached_derivatives_follow_path() {
+        let mut myspace = uniform_space(2.0, GRAVITY_MPS2);
+        myspace.new_ball_unchecked(0.0, 5.0, 3.0, 4.0, 1.0, 1.0, 0.5, 1.0, [1.0,1.0,1.0,1.0]);
+        let ball = &myspace.balls[0];
+        assert_eq!((ball.get_vx(0.1), ball.get_vy(0.1)), (3.0 + 2.0 * 0.1, 4.0 + GRAVITY_MPS2 * 0.1));
+        assert_eq!((ball.get_ax(0.1), ball.get_ay(0.1)), (2.0, GRAVITY_MPS2));
+        //re-anchoring the path has to refresh the derivatives along with it
+        myspace.tick(1.0 / 165.0);
+        myspace.redirect(0, -1.0, 0.0);
+        let (ball, t) = (&myspace.balls[0], myspace.get_elapsed());
+        assert_eq!((ball.get_vx(t), ball.get_vy(t)), (-1.0, 0.0));
+        assert!((ball.get_vx(t + 0.5) - (-1.0 + 2.0 * 0.5)).abs() < 1e-5);
+    }
+    #[test]
+    #[cfg_attr(not(feature = "f64"), ignore = "f32 can't resolve a tick a day into a run, build with the f64 feature")]
+    fn long_runs_keep_precision() {
+        //a slow drifter carried a bit over a day into the run, then steered and stepped at the usual tick rate
+        let mut myspace = open_space();
+        myspace.new_ball_unchecked(-50.0, 0.0, 5e-4, 0.0, 1.0, 1.0, 1.0, 1.0, [1.0,1.0,1.0,1.0]);
+        for _ in 0..10000 {
+            myspace.tick(10.0);
+        }
+        let start = myspace.get_elapsed();
+        assert!((myspace.balls[0].get_x() - (-50.0 + 5e-4 * start)).abs() < 1e-6);
+        myspace.redirect(0, 2.0, 0.0);
+        let x = myspace.balls[0].get_x();
+        for _ in 0..165 {
+            myspace.tick(1.0 / 165.0);
+        }
+        let t = myspace.get_elapsed();
+        assert!((t - start - 1.0).abs() < 1e-9);
+        assert!((myspace.balls[0].get_x() - (x + 2.0)).abs() < 1e-6);
+        assert_eq!(myspace.balls[0].get_vx(t), 2.0);
+    }
     #[test]
     fn gravity_space_collision() {
         let mps2 : Units = Unit::M.units() / Unit::S.units() / Unit::S.units();
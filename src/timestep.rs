@@ -0,0 +1,87 @@
+//timestep.rs
+//fixed timestep accumulator, so the simulation advances by the same dt however often frames come in
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#![allow(dead_code)]
+use super::kinematics::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTimestep {
+    dt : Float,
+    max_substeps : usize, //cap on steps a frame, so a slow frame can't snowball into slower ones
+    accumulator : Float, //real time passed that hasn't been stepped yet
+    dropped : Float, //real time thrown away by the cap, the simulation runs behind the clock by this much
+}
+
+impl FixedTimestep {
+    pub fn new(dt : Float, max_substeps : usize) -> FixedTimestep {
+        FixedTimestep { dt, max_substeps : max_substeps.max(1), accumulator : 0.0, dropped : 0.0 }
+    }
+    pub fn get_dt(&self) -> Float {
+        self.dt
+    }
+    pub fn get_max_substeps(&self) -> usize {
+        self.max_substeps
+    }
+    pub fn set_max_substeps(&mut self, max_substeps : usize) {
+        self.max_substeps = max_substeps.max(1);
+    }
+    pub fn get_dropped(&self) -> Float {
+        self.dropped
+    }
+    pub fn advance(&mut self, frame_dt : Float, mut step : impl FnMut(Float)) -> usize {
+        //banks frame_dt and calls step(dt) once per whole dt banked, returns how many steps were taken
+        self.accumulator += frame_dt.max(0.0);
+        let mut steps = 0;
+        while self.accumulator >= self.dt && steps < self.max_substeps {
+            step(self.dt);
+            self.accumulator -= self.dt;
+            steps += 1;
+        }
+        if self.accumulator >= self.dt {
+            //past the cap, keep only the partial step so alpha stays meaningful
+            let kept = self.accumulator % self.dt;
+            self.dropped += self.accumulator - kept;
+            self.accumulator = kept;
+        }
+        steps
+    }
+    pub fn alpha(&self) -> Float {
+        //how far between the last step and the next the clock is, 0 to 1, for interpolating what gets drawn
+        (self.accumulator / self.dt).clamp(0.0, 1.0)
+    }
+}
+
+pub fn lerp(from : (Float, Float), to : (Float, Float), alpha : Float) -> (Float, Float) {
+    (from.0 + (to.0 - from.0) * alpha, from.1 + (to.1 - from.1) * alpha)
+}
+
+//~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn frame_rate_independent() {
+        //a second of frames at very different rates runs the same number of steps
+        for fps in [24, 60, 144, 1000] {
+            let mut timestep = FixedTimestep::new(1.0 / 165.0, 16);
+            let mut total = 0;
+            for _ in 0..fps {
+                total += timestep.advance(1.0 / fps as Float, |dt| assert_eq!(dt, 1.0 / 165.0));
+                assert!((0.0..1.0).contains(&timestep.alpha()));
+            }
+            assert!((164..=165).contains(&total), "{} fps ran {} steps", fps, total);
+        }
+    }
+    #[test]
+    fn substeps_capped() {
+        let mut timestep = FixedTimestep::new(0.01, 4);
+        assert_eq!(timestep.advance(0.105, |_| ()), 4);
+        assert!((timestep.get_dropped() - 0.06).abs() < 1e-5);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-3);
+        assert_eq!(timestep.advance(0.005, |_| ()), 1);
+        assert_eq!(lerp((0.0, 2.0), (4.0, 0.0), 0.25), (1.0, 1.5));
+    }
+}